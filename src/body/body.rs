@@ -1,5 +1,6 @@
 //!  暂时弃用
 
+#[allow(unused)]
 pub trait Body {
     fn into_body(self) -> Vec<u8>;
 }
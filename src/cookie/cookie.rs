@@ -0,0 +1,419 @@
+//! Cookie
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    error::CookieError,
+    utils::{
+        date,
+        percent::{hex_value, percent_decode},
+    },
+};
+
+/// `SameSite` 属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl std::fmt::Display for SameSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Strict => "Strict",
+            Self::Lax => "Lax",
+            Self::None => "None",
+        };
+        f.write_str(s)
+    }
+}
+
+/// 一个 cookie 及其属性
+///
+/// `Display` 输出 `Set-Cookie` 头部的值。`SameSite=None` 和 `Partitioned`
+/// 要求同时设置 `Secure`, 输出时会自动加上。
+/// 值中不属于 cookie-octet (RFC 6265) 的字节和 `%` 输出时按百分号编码, 解析时解码
+///
+/// # Example
+/// ```rust
+/// use http_sv::cookie::{Cookie, SameSite};
+///
+/// let cookie = Cookie::new("id", "42")
+///     .path("/")
+///     .http_only(true)
+///     .same_site(SameSite::Lax);
+/// assert_eq!(cookie.to_string(), "id=42; Path=/; HttpOnly; SameSite=Lax");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    domain: Option<String>,
+    path: Option<String>,
+    expires: Option<DateTime<Utc>>,
+    max_age: Option<Duration>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    partitioned: bool,
+}
+
+impl Cookie {
+    /// # Panics
+    ///
+    /// 名称不是 RFC 6265 的 token 时 panic
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        assert!(is_token(&name), "cookie 名称 `{name}` 不是合法的 token");
+        Self::new_unchecked(name, value.into())
+    }
+
+    fn new_unchecked(name: String, value: String) -> Self {
+        Self {
+            name,
+            value,
+            domain: None,
+            path: None,
+            expires: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+            partitioned: false,
+        }
+    }
+
+    /// 用于删除 cookie, 值为空并且立即过期
+    pub fn removal(name: impl Into<String>) -> Self {
+        Self::new(name, "")
+            .max_age(Duration::zero())
+            .expires(DateTime::UNIX_EPOCH)
+    }
+
+    /// 解析 `Set-Cookie` 头部的值, 不认识的属性会被忽略
+    pub fn parse(s: &str) -> Result<Self, CookieError> {
+        let mut parts = s.split(';');
+        let (name, value) = parse_pair(parts.next().unwrap_or_default())?;
+        let mut cookie = Self::new_unchecked(name.to_string(), decode_value(value));
+        for part in parts {
+            let (key, value) = match part.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (part.trim(), ""),
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "domain" if !is_attribute_value(value) => {
+                    return Err(CookieError::InvalidAttribute("Domain"));
+                }
+                "domain" => cookie.domain = Some(value.trim_start_matches('.').to_string()),
+                "path" if !is_attribute_value(value) => {
+                    return Err(CookieError::InvalidAttribute("Path"));
+                }
+                "path" => cookie.path = Some(value.to_string()),
+                "expires" => {
+                    cookie.expires =
+                        Some(date::parse(value).ok_or(CookieError::InvalidAttribute("Expires"))?)
+                }
+                "max-age" => {
+                    let seconds: i64 = value
+                        .parse()
+                        .map_err(|_| CookieError::InvalidAttribute("Max-Age"))?;
+                    cookie.max_age = Some(Duration::seconds(seconds.max(0)));
+                }
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => {
+                    cookie.same_site = Some(match value.to_ascii_lowercase().as_str() {
+                        "strict" => SameSite::Strict,
+                        "lax" => SameSite::Lax,
+                        "none" => SameSite::None,
+                        _ => return Err(CookieError::InvalidAttribute("SameSite")),
+                    })
+                }
+                "partitioned" => cookie.partitioned = true,
+                _ => {}
+            }
+        }
+        Ok(cookie)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+    }
+
+    /// # Panics
+    ///
+    /// 包含 `;` 或控制字符 (包括 CR、LF) 时 panic
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        let domain = domain.into();
+        assert!(
+            is_attribute_value(&domain),
+            "cookie 的 Domain 不能包含 `;` 或控制字符"
+        );
+        self.domain = Some(domain);
+        self
+    }
+
+    pub fn domain_ref(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    /// # Panics
+    ///
+    /// 包含 `;` 或控制字符 (包括 CR、LF) 时 panic
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        assert!(
+            is_attribute_value(&path),
+            "cookie 的 Path 不能包含 `;` 或控制字符"
+        );
+        self.path = Some(path);
+        self
+    }
+
+    pub fn path_ref(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// 过期时间, 输出时精确到秒
+    pub fn expires(mut self, expires: DateTime<Utc>) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    pub fn expires_ref(&self) -> Option<&DateTime<Utc>> {
+        self.expires.as_ref()
+    }
+
+    /// 有效时长, 同时设置时浏览器优先使用 `Max-Age`
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn max_age_ref(&self) -> Option<&Duration> {
+        self.max_age.as_ref()
+    }
+
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    pub fn is_http_only(&self) -> bool {
+        self.http_only
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    pub fn same_site_ref(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    /// CHIPS 分区 cookie
+    pub fn partitioned(mut self, partitioned: bool) -> Self {
+        self.partitioned = partitioned;
+        self
+    }
+
+    pub fn is_partitioned(&self) -> bool {
+        self.partitioned
+    }
+}
+
+impl std::fmt::Display for Cookie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, encode_value(&self.value))?;
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={path}")?;
+        }
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", date::format(expires))?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.num_seconds())?;
+        }
+        if self.secure || self.partitioned || self.same_site == Some(SameSite::None) {
+            f.write_str("; Secure")?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={same_site}")?;
+        }
+        if self.partitioned {
+            f.write_str("; Partitioned")?;
+        }
+        Ok(())
+    }
+}
+
+/// RFC 7230 的 token, 不能包含分隔符、空白和控制字符
+pub(crate) fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_graphic() && !br#"()<>@,;:\"/[]?={}"#.contains(&b))
+}
+
+/// 属性值不能包含 `;` 和控制字符, 否则会添加属性或拆分响应头
+fn is_attribute_value(s: &str) -> bool {
+    !s.chars().any(|c| c == ';' || c.is_control())
+}
+
+/// RFC 6265 的 cookie-octet, `%` 用于编码, 也需要编码
+fn is_cookie_octet(b: u8) -> bool {
+    matches!(b, 0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E) && b != b'%'
+}
+
+fn encode_value(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.bytes().all(is_cookie_octet) {
+        return value.into();
+    }
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if is_cookie_octet(b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out.into()
+}
+
+/// 解码 `%XX`, 解码结果不是 UTF-8 时保留原值
+pub(crate) fn decode_value(value: &str) -> String {
+    let escaped = value
+        .as_bytes()
+        .windows(3)
+        .any(|w| w[0] == b'%' && hex_value(w[1]).is_some() && hex_value(w[2]).is_some());
+    if !escaped {
+        return value.to_string();
+    }
+    String::from_utf8(percent_decode(value.as_bytes())).unwrap_or_else(|_| value.to_string())
+}
+
+/// 解析 `name=value`, 去掉值两边的双引号
+pub(crate) fn parse_pair(s: &str) -> Result<(&str, &str), CookieError> {
+    let (name, value) = s.split_once('=').ok_or(CookieError::MissingPair)?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CookieError::MissingPair);
+    }
+    if !is_token(name) {
+        return Err(CookieError::InvalidName(name.to_string()));
+    }
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+    Ok((name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::{Cookie, SameSite};
+
+    #[test]
+    fn test_display() {
+        let cookie = Cookie::new("id", "a3fWa")
+            .domain("example.com")
+            .path("/docs")
+            .expires(Utc.with_ymd_and_hms(2015, 10, 21, 7, 28, 0).unwrap())
+            .max_age(Duration::hours(1))
+            .http_only(true)
+            .same_site(SameSite::None)
+            .partitioned(true);
+        assert_eq!(
+            cookie.to_string(),
+            "id=a3fWa; Domain=example.com; Path=/docs; Expires=Wed, 21 Oct 2015 07:28:00 GMT; \
+             Max-Age=3600; Secure; HttpOnly; SameSite=None; Partitioned"
+        );
+        assert_eq!(
+            Cookie::parse(&cookie.to_string()).unwrap(),
+            cookie.secure(true)
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let cookie =
+            Cookie::parse("sid=\"abc\"; path=/; max-age=-5; secure; samesite=lax; Foo").unwrap();
+        assert_eq!(cookie.name(), "sid");
+        assert_eq!(cookie.value(), "abc");
+        assert_eq!(cookie.path_ref(), Some("/"));
+        assert_eq!(cookie.max_age_ref(), Some(&Duration::zero()));
+        assert!(cookie.is_secure());
+        assert_eq!(cookie.same_site_ref(), Some(SameSite::Lax));
+
+        assert!(Cookie::parse("novalue").is_err());
+        assert!(Cookie::parse("a=1; SameSite=Sometimes").is_err());
+    }
+
+    #[test]
+    fn test_removal() {
+        let cookie = Cookie::removal("sid").path("/");
+        assert_eq!(
+            cookie.to_string(),
+            "sid=; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0"
+        );
+    }
+
+    #[test]
+    fn test_value_encoding() {
+        let cookie = Cookie::new("a", "x; Secure\r\nX-Injected: 1%");
+        assert_eq!(
+            cookie.to_string(),
+            "a=x%3B%20Secure%0D%0AX-Injected:%201%25"
+        );
+        assert_eq!(Cookie::parse(&cookie.to_string()).unwrap(), cookie);
+        assert_eq!(Cookie::new("a", "b64+/=").to_string(), "a=b64+/=");
+    }
+
+    #[test]
+    fn test_rejections() {
+        assert!(Cookie::parse("a b=1").is_err());
+        assert!(Cookie::parse("a=1; Path=/\r\nX-Injected: 1").is_err());
+        assert!(Cookie::parse("a=1; Domain=a\nb.com").is_err());
+
+        let panics = |f: fn()| std::panic::catch_unwind(f).is_err();
+        assert!(panics(|| {
+            Cookie::new("a;b", "1");
+        }));
+        assert!(panics(|| {
+            Cookie::new("", "1");
+        }));
+        assert!(panics(|| {
+            Cookie::new("a", "1").domain("a.com\r\nX-Injected: 1");
+        }));
+        assert!(panics(|| {
+            Cookie::new("a", "1").path("/; HttpOnly");
+        }));
+    }
+}
@@ -0,0 +1,259 @@
+//! 请求中的 cookie
+
+use std::{collections::HashMap, convert::Infallible};
+
+use chrono::{DateTime, Duration};
+
+use crate::{extract::FromRequestParts, headers::Headers, request::Request};
+
+use super::{
+    Cookie, Key,
+    cookie::{decode_value, parse_pair},
+};
+
+#[derive(Debug, Clone)]
+struct Change {
+    cookie: Cookie,
+    removed: bool,
+}
+
+/// 请求中的 cookie 和本次响应要设置的 cookie
+///
+/// 作为提取器时解析请求的 `Cookie` 头部, 格式错误的项会被忽略。
+/// 通过 [`Response::cookies`](crate::response::Response::cookies) 把修改写入响应
+///
+/// # Example
+/// ```rust
+/// use http_sv::{
+///     Router,
+///     cookie::{Cookie, CookieJar},
+///     response::Response,
+/// };
+///
+/// fn visit(mut jar: CookieJar) -> Response {
+///     let n: u32 = jar
+///         .get("visits")
+///         .and_then(|c| c.value().parse().ok())
+///         .unwrap_or(0);
+///     jar.add(Cookie::new("visits", (n + 1).to_string()).path("/"));
+///     Response::new().cookies(&jar).body(format!("visit {}", n + 1).into_bytes())
+/// }
+///
+/// let app = Router::new().route("/", "GET", visit);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+    original: HashMap<String, Cookie>,
+    delta: Vec<Change>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 解析 `Cookie` 头部, 例如 `a=1; b=2`
+    pub fn parse(header: &str) -> Self {
+        let original = header
+            .split(';')
+            .filter_map(|pair| parse_pair(pair).ok())
+            .map(|(name, value)| (name.to_string(), Cookie::new(name, decode_value(value))))
+            .collect();
+        Self {
+            original,
+            delta: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Cookie> {
+        match self
+            .delta
+            .iter()
+            .find(|change| change.cookie.name() == name)
+        {
+            Some(change) if change.removed => None,
+            Some(change) => Some(&change.cookie),
+            None => self.original.get(name),
+        }
+    }
+
+    /// 添加或替换 cookie
+    pub fn add(&mut self, cookie: Cookie) {
+        self.delta
+            .retain(|change| change.cookie.name() != cookie.name());
+        self.delta.push(Change {
+            cookie,
+            removed: false,
+        });
+    }
+
+    /// 删除 cookie, `Domain` 和 `Path` 需要和设置时一致
+    pub fn remove(&mut self, cookie: Cookie) {
+        let mut cookie = cookie
+            .max_age(Duration::zero())
+            .expires(DateTime::UNIX_EPOCH);
+        cookie.set_value("");
+        self.delta
+            .retain(|change| change.cookie.name() != cookie.name());
+        self.delta.push(Change {
+            cookie,
+            removed: true,
+        });
+    }
+
+    /// 当前所有有效的 cookie
+    pub fn iter(&self) -> impl Iterator<Item = &Cookie> {
+        let added = self
+            .delta
+            .iter()
+            .filter(|change| !change.removed)
+            .map(|change| &change.cookie);
+        let original = self.original.values().filter(|cookie| {
+            self.delta
+                .iter()
+                .all(|change| change.cookie.name() != cookie.name())
+        });
+        added.chain(original)
+    }
+
+    /// 需要通过 `Set-Cookie` 发送的 cookie
+    pub fn delta(&self) -> impl Iterator<Item = &Cookie> {
+        self.delta.iter().map(|change| &change.cookie)
+    }
+
+    /// 签名的 cookie, 客户端可以读取但不能修改
+    pub fn signed<'a>(&'a mut self, key: &'a Key) -> SignedJar<'a> {
+        SignedJar { jar: self, key }
+    }
+
+    /// 加密的 cookie, 客户端不能读取也不能修改
+    pub fn private<'a>(&'a mut self, key: &'a Key) -> PrivateJar<'a> {
+        PrivateJar { jar: self, key }
+    }
+}
+
+impl FromRequestParts for CookieJar {
+    type Rejection = Infallible;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        let header = req
+            .headers
+            .get(&Headers::Cookie.to_string())
+            .unwrap_or_default();
+        Ok(Self::parse(header))
+    }
+}
+
+/// 通过 [`CookieJar::signed`] 获取
+#[derive(Debug)]
+pub struct SignedJar<'a> {
+    jar: &'a mut CookieJar,
+    key: &'a Key,
+}
+
+impl SignedJar<'_> {
+    /// 签名正确时返回原始值, 否则返回 `None`
+    pub fn get(&self, name: &str) -> Option<Cookie> {
+        let cookie = self.jar.get(name)?;
+        let value = self.key.verify(name, cookie.value())?;
+        let mut cookie = cookie.clone();
+        cookie.set_value(value);
+        Some(cookie)
+    }
+
+    pub fn add(&mut self, mut cookie: Cookie) {
+        let value = self.key.sign(cookie.name(), cookie.value());
+        cookie.set_value(value);
+        self.jar.add(cookie);
+    }
+
+    pub fn remove(&mut self, cookie: Cookie) {
+        self.jar.remove(cookie);
+    }
+}
+
+/// 通过 [`CookieJar::private`] 获取
+#[derive(Debug)]
+pub struct PrivateJar<'a> {
+    jar: &'a mut CookieJar,
+    key: &'a Key,
+}
+
+impl PrivateJar<'_> {
+    /// 解密成功时返回原始值, 否则返回 `None`
+    pub fn get(&self, name: &str) -> Option<Cookie> {
+        let cookie = self.jar.get(name)?;
+        let value = self.key.decrypt(name, cookie.value())?;
+        let mut cookie = cookie.clone();
+        cookie.set_value(value);
+        Some(cookie)
+    }
+
+    pub fn add(&mut self, mut cookie: Cookie) {
+        let value = self.key.encrypt(cookie.name(), cookie.value());
+        cookie.set_value(value);
+        self.jar.add(cookie);
+    }
+
+    pub fn remove(&mut self, cookie: Cookie) {
+        self.jar.remove(cookie);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CookieJar;
+    use crate::{
+        cookie::{Cookie, Key},
+        extract::FromRequestParts,
+        headers::Headers,
+        request::Request,
+        response::Response,
+    };
+
+    #[test]
+    fn test_jar() {
+        let mut req = Request::new().headers(Headers::Cookie, "a=1; b=\"2\"; broken; c=3");
+        let mut jar = CookieJar::from_request_parts(&mut req).unwrap();
+        assert_eq!(jar.get("a").unwrap().value(), "1");
+        assert_eq!(jar.get("b").unwrap().value(), "2");
+        assert!(jar.get("broken").is_none());
+
+        jar.add(Cookie::new("a", "10"));
+        jar.remove(Cookie::new("b", "").path("/"));
+        assert_eq!(jar.get("a").unwrap().value(), "10");
+        assert!(jar.get("b").is_none());
+        assert_eq!(jar.iter().count(), 2);
+
+        let resp: Vec<u8> = Response::new().cookies(&jar).into();
+        let resp = String::from_utf8(resp).unwrap();
+        assert!(resp.contains("\r\nSet-Cookie: a=10\r\n"));
+        assert!(resp.contains(
+            "\r\nSet-Cookie: b=; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0\r\n"
+        ));
+    }
+
+    #[test]
+    fn test_signed_and_private() {
+        let key = Key::generate();
+        let mut jar = CookieJar::new();
+        jar.signed(&key).add(Cookie::new("user", "bing"));
+        jar.private(&key).add(Cookie::new("token", "secret"));
+
+        let header = jar
+            .delta()
+            .map(|cookie| format!("{}={}", cookie.name(), cookie.value()))
+            .collect::<Vec<_>>()
+            .join("; ");
+        assert!(!header.contains("secret"));
+
+        let mut jar = CookieJar::parse(&header);
+        assert_eq!(jar.signed(&key).get("user").unwrap().value(), "bing");
+        assert_eq!(jar.private(&key).get("token").unwrap().value(), "secret");
+        assert!(jar.signed(&key).get("token").is_none());
+
+        let tampered = header.replace("bing", "root");
+        let jar = &mut CookieJar::parse(&tampered);
+        assert!(jar.signed(&key).get("user").is_none());
+    }
+}
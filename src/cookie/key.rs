@@ -0,0 +1,135 @@
+//! 签名和加密 cookie 使用的密钥
+
+use aes_gcm::{
+    Aes256Gcm, KeyInit, Nonce,
+    aead::{Aead, Payload},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::error::CookieError;
+
+/// 密钥的最小长度
+const KEY_LEN: usize = 64;
+const NONCE_LEN: usize = 12;
+
+/// cookie 密钥
+///
+/// 前 32 字节用于 HMAC-SHA256 签名, 后 32 字节用于 AES-256-GCM 加密。
+/// 多个服务实例需要使用同一个密钥, 通过 [`Router::with_state`](crate::Router::with_state)
+/// 共享给处理函数
+#[derive(Clone)]
+pub struct Key {
+    signing: [u8; 32],
+    encryption: [u8; 32],
+}
+
+impl Key {
+    /// 从至少 64 字节的随机数据创建密钥
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CookieError> {
+        if bytes.len() < KEY_LEN {
+            return Err(CookieError::KeyTooShort(bytes.len()));
+        }
+        let mut signing = [0; 32];
+        let mut encryption = [0; 32];
+        signing.copy_from_slice(&bytes[..32]);
+        encryption.copy_from_slice(&bytes[32..64]);
+        Ok(Self {
+            signing,
+            encryption,
+        })
+    }
+
+    /// 生成随机密钥
+    pub fn generate() -> Self {
+        let mut bytes = [0; KEY_LEN];
+        getrandom::getrandom(&mut bytes).expect("无法获取系统随机数");
+        Self::from_bytes(&bytes).unwrap()
+    }
+
+    fn mac(&self, name: &str) -> Hmac<Sha256> {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.signing).unwrap();
+        mac.update(name.as_bytes());
+        mac.update(b"=");
+        mac
+    }
+
+    /// 签名后的值: `base64(hmac).value`
+    pub(crate) fn sign(&self, name: &str, value: &str) -> String {
+        let mut mac = self.mac(name);
+        mac.update(value.as_bytes());
+        let tag = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+        format!("{tag}.{value}")
+    }
+
+    pub(crate) fn verify<'a>(&self, name: &str, signed: &'a str) -> Option<&'a str> {
+        let (tag, value) = signed.split_once('.')?;
+        let tag = URL_SAFE_NO_PAD.decode(tag).ok()?;
+        let mut mac = self.mac(name);
+        mac.update(value.as_bytes());
+        mac.verify_slice(&tag).ok()?;
+        Some(value)
+    }
+
+    /// 加密后的值: `base64(nonce + ciphertext)`, cookie 名称作为附加数据
+    pub(crate) fn encrypt(&self, name: &str, value: &str) -> String {
+        let mut nonce = [0; NONCE_LEN];
+        getrandom::getrandom(&mut nonce).expect("无法获取系统随机数");
+        let cipher = Aes256Gcm::new(&self.encryption.into());
+        let payload = Payload {
+            msg: value.as_bytes(),
+            aad: name.as_bytes(),
+        };
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .expect("加密失败");
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        URL_SAFE_NO_PAD.encode(data)
+    }
+
+    pub(crate) fn decrypt(&self, name: &str, encrypted: &str) -> Option<String> {
+        let data = URL_SAFE_NO_PAD.decode(encrypted).ok()?;
+        if data.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let cipher = Aes256Gcm::new(&self.encryption.into());
+        let payload = Payload {
+            msg: ciphertext,
+            aad: name.as_bytes(),
+        };
+        let value = cipher.decrypt(Nonce::from_slice(nonce), payload).ok()?;
+        String::from_utf8(value).ok()
+    }
+}
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Key").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Key;
+
+    #[test]
+    fn test_sign_and_encrypt() {
+        let key = Key::generate();
+        let signed = key.sign("user", "bing");
+        assert_eq!(key.verify("user", &signed), Some("bing"));
+        assert_eq!(key.verify("admin", &signed), None);
+        assert_eq!(key.verify("user", &signed.replace("bing", "root")), None);
+        assert_eq!(Key::generate().verify("user", &signed), None);
+
+        let encrypted = key.encrypt("user", "bing");
+        assert!(!encrypted.contains("bing"));
+        assert_eq!(key.decrypt("user", &encrypted).as_deref(), Some("bing"));
+        assert_eq!(key.decrypt("admin", &encrypted), None);
+        assert_eq!(key.decrypt("user", "AAAA"), None);
+
+        assert!(Key::from_bytes(&[0; 32]).is_err());
+    }
+}
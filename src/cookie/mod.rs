@@ -0,0 +1,11 @@
+//! Cookie
+//!
+//! 解析请求的 `Cookie` 头部, 生成 `Set-Cookie` 头部, 支持签名和加密的 cookie
+
+mod cookie;
+mod jar;
+mod key;
+
+pub use cookie::{Cookie, SameSite};
+pub use jar::{CookieJar, PrivateJar, SignedJar};
+pub use key::Key;
@@ -2,6 +2,7 @@
 
 use thiserror::Error;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
 pub enum ParseError {
     #[error("解析分隔符失败")]
@@ -19,12 +20,108 @@ pub enum ParseError {
 #[derive(Debug, Error)]
 pub enum ResponseError {}
 
+#[derive(Debug, Error, PartialEq)]
+pub enum TargetError {
+    #[error("无法识别的请求目标")]
+    InvalidTarget,
+    #[error("请求目标必须以 `/` 开头")]
+    NotAbsolutePath,
+    #[error("路径不是合法的 UTF-8")]
+    InvalidUtf8,
+    #[error("路径包含编码的 NUL")]
+    EncodedNul,
+    #[error("路径包含编码的 `/`")]
+    EncodedSlash,
+}
+
+#[derive(Debug, Error)]
+pub enum UrlEncodedError {
+    #[error("urlencoded 格式错误--> {0}")]
+    Custom(String),
+}
+
+impl serde::de::Error for UrlEncodedError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+impl serde::ser::Error for UrlEncodedError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+/// cookie 错误
+#[derive(Debug, Error)]
+pub enum CookieError {
+    #[error("cookie 缺少名称或值")]
+    MissingPair,
+    #[error("cookie 名称 `{0}` 不是合法的 token")]
+    InvalidName(String),
+    #[error("cookie 属性 `{0}` 的值错误")]
+    InvalidAttribute(&'static str),
+    #[error("密钥至少需要 64 字节, 实际为 {0} 字节")]
+    KeyTooShort(usize),
+}
+
+/// 会话存储错误
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("会话读写失败--> {0}")]
+    Io(#[from] std::io::Error),
+    #[error("会话数据序列化失败--> {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// 解析 `multipart/form-data` 请求体的错误
+#[derive(Debug, Error)]
+pub enum MultipartError {
+    #[error("单个字段超过 {0} 字节")]
+    PartTooLarge(usize),
+    #[error("请求体超过 {0} 字节")]
+    BodyTooLarge(usize),
+    #[error("字段头部过长")]
+    HeaderTooLarge,
+    #[error("请求体格式错误--> {0}")]
+    Malformed(&'static str),
+    #[error("字段内容不是合法的 UTF-8")]
+    InvalidUtf8,
+    #[error("读写失败--> {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// WebSocket 连接错误
+#[derive(Debug, Error)]
+pub enum WebSocketError {
+    #[error("读写连接失败--> {0}")]
+    Io(#[from] std::io::Error),
+    #[error("协议错误--> {0}")]
+    Protocol(&'static str),
+    #[error("文本消息不是合法的 UTF-8")]
+    InvalidUtf8,
+    #[error("消息超过 {0} 字节")]
+    MessageTooLarge(usize),
+    #[error("连接已关闭")]
+    ConnectionClosed,
+}
+
 #[derive(Debug, Error)]
 pub enum RequestError {
     #[error("读取请求失败")]
     ReadRequestErr,
     #[error("请求为空")]
     EmptyRequest,
+    #[error("请求头过长")]
+    HeaderTooLarge,
+    #[error("Content-Length 不合法")]
+    InvalidContentLength,
+    #[error("请求体超过 {0} 字节")]
+    BodyTooLarge(usize),
+    #[error("读取请求体失败--> {0}")]
+    ReadBodyErr(#[from] std::io::Error),
     #[error("解析错误--> {0}")]
     ParseError(#[from] ParseError),
+    #[error("请求目标错误--> {0}")]
+    TargetError(#[from] TargetError),
 }
@@ -0,0 +1,158 @@
+//! URL 编码的表单
+
+use std::collections::HashMap;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    headers::{Headers, HttpMethod, Mime, StatusCode},
+    request::Request,
+    response::{IntoResponse, Response},
+    utils::urlencoded,
+};
+
+use super::{FromRequest, Rejection, content_type};
+
+/// `application/x-www-form-urlencoded` 表单
+///
+/// 作为提取器时, `GET` 和 `HEAD` 请求读取查询字符串, 其他请求要求
+/// `Content-Type` 为 `application/x-www-form-urlencoded` 并读取请求体,
+/// 重复的键可以反序列化为 `Vec` 字段;
+/// 作为响应时把 `T` 编码为表单
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, extract::Form};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Signup {
+///     email: String,
+///     interests: Vec<String>,
+/// }
+///
+/// fn signup(Form(form): Form<Signup>) -> String {
+///     format!("{} {:?}", form.email, form.interests)
+/// }
+///
+/// let app = Router::new().route("/signup", "POST", signup);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Form<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Form<T> {
+    type Rejection = Rejection;
+
+    fn from_request(req: Request) -> Result<Self, Self::Rejection> {
+        if matches!(req.method_ref(), HttpMethod::GET | HttpMethod::HEAD) {
+            return req.query().map(Form).map_err(Rejection::InvalidForm);
+        }
+
+        let form_type = Mime::ApplicationWwwFormUrlencoded.to_string();
+        if content_type(&req).as_deref() != Some(form_type.as_str()) {
+            return Err(Rejection::MissingFormContentType);
+        }
+        let body = std::str::from_utf8(req.body_ref()).map_err(|_| Rejection::InvalidUtf8)?;
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for (key, value) in urlencoded::parse(body) {
+            map.entry(key).or_default().push(value);
+        }
+        urlencoded::from_map(&map)
+            .map(Form)
+            .map_err(Rejection::InvalidForm)
+    }
+}
+
+impl<T: Serialize> IntoResponse for Form<T> {
+    fn into_response(self) -> Response {
+        match urlencoded::to_string(&self.0) {
+            Ok(body) => Response::new()
+                .header(
+                    Headers::ContentType,
+                    &Mime::ApplicationWwwFormUrlencoded.to_string(),
+                )
+                .raw_body(body.into_bytes()),
+            Err(e) => Response::new()
+                .status(StatusCode::InternalServerError)
+                .body(e.to_string().into_bytes()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::Form;
+    use crate::{
+        extract::FromRequest,
+        headers::Headers,
+        request::Request,
+        response::{IntoResponse, Response},
+    };
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Signup {
+        email: String,
+        interests: Vec<String>,
+        age: Option<u32>,
+    }
+
+    fn body(resp: Response) -> String {
+        let buf: Vec<u8> = resp.into();
+        String::from_utf8_lossy(&buf).to_string()
+    }
+
+    fn form_request(body: &str) -> Request {
+        let mut req = Request::new().method("POST").headers(
+            Headers::ContentType,
+            "application/x-www-form-urlencoded; charset=utf-8",
+        );
+        req.body = body.as_bytes().to_vec();
+        req
+    }
+
+    #[test]
+    fn test_form_extract() {
+        let req = form_request("email=a%40b.com&interests=rust&interests=go+lang&age=3");
+        let Form(form) = Form::<Signup>::from_request(req).ok().unwrap();
+        assert_eq!(
+            form,
+            Signup {
+                email: "a@b.com".to_string(),
+                interests: vec!["rust".to_string(), "go lang".to_string()],
+                age: Some(3),
+            }
+        );
+
+        let req = Request::new().path("/signup?email=x&interests=a").unwrap();
+        let Form(form) = Form::<Signup>::from_request(req).ok().unwrap();
+        assert_eq!(form.email, "x");
+        assert_eq!(form.interests, ["a"]);
+    }
+
+    #[test]
+    fn test_form_rejection() {
+        let mut req = Request::new().method("POST");
+        req.body = b"email=x".to_vec();
+        let rejection = Form::<Signup>::from_request(req).err().unwrap();
+        assert!(body(rejection.into_response()).starts_with("HTTP/1.1 415"));
+
+        let req = form_request("email=x&age=old");
+        let rejection = Form::<Signup>::from_request(req).err().unwrap();
+        assert!(body(rejection.into_response()).starts_with("HTTP/1.1 422"));
+    }
+
+    #[test]
+    fn test_form_response() {
+        let form = Form(Signup {
+            email: "a@b.com".to_string(),
+            interests: vec!["rust".to_string(), "go".to_string()],
+            age: None,
+        });
+        let resp = body(form.into_response());
+        assert!(resp.contains("Content-Type: application/x-www-form-urlencoded\r\n"));
+        assert!(resp.contains("Content-Length: 43\r\n"));
+        assert!(resp.ends_with("\r\n\r\nemail=a%40b.com&interests=rust&interests=go"));
+    }
+}
@@ -0,0 +1,148 @@
+//! JSON
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    headers::{Headers, Mime, StatusCode},
+    request::Request,
+    response::{IntoResponse, Response},
+};
+
+use super::{FromRequest, Rejection, content_type};
+
+/// JSON 请求体和响应体
+///
+/// 作为提取器时要求 `Content-Type` 为 `application/json` 或 `application/*+json`,
+/// 否则返回 415, 反序列化失败返回 422;
+/// 作为响应时序列化 `T` 并设置 `Content-Type: application/json`
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, extract::Json};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// fn create(Json(user): Json<User>) -> Json<User> {
+///     Json(user)
+/// }
+///
+/// let app = Router::new().route("/users", "POST", create);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Json<T> {
+    type Rejection = Rejection;
+
+    fn from_request(req: Request) -> Result<Self, Self::Rejection> {
+        if !is_json(content_type(&req).as_deref()) {
+            return Err(Rejection::MissingJsonContentType);
+        }
+        let value = serde_json::from_slice(req.body_ref())?;
+        Ok(Json(value))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        match serde_json::to_vec(&self.0) {
+            Ok(body) => Response::new()
+                .header(Headers::ContentType, &Mime::ApplicationJson.to_string())
+                .raw_body(body),
+            Err(e) => Response::new()
+                .status(StatusCode::InternalServerError)
+                .body(e.to_string().into_bytes()),
+        }
+    }
+}
+
+fn is_json(mime: Option<&str>) -> bool {
+    match mime.and_then(|mime| mime.split_once('/')) {
+        Some(("application", subtype)) => subtype == "json" || subtype.ends_with("+json"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::{Json, is_json};
+    use crate::{
+        extract::FromRequest,
+        headers::Headers,
+        request::Request,
+        response::{IntoResponse, Response},
+    };
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    fn body(resp: Response) -> String {
+        let buf: Vec<u8> = resp.into();
+        String::from_utf8_lossy(&buf).to_string()
+    }
+
+    fn json_request(body: &str) -> Request {
+        Request::new()
+            .method("POST")
+            .headers(Headers::ContentType, "application/json; charset=utf-8")
+            .body(body)
+    }
+
+    #[test]
+    fn test_is_json() {
+        assert!(is_json(Some("application/json")));
+        assert!(is_json(Some("application/problem+json")));
+        assert!(!is_json(Some("text/plain")));
+        assert!(!is_json(Some("application/jsonx")));
+        assert!(!is_json(None));
+    }
+
+    #[test]
+    fn test_json_extract() {
+        let Json(user) = Json::<User>::from_request(json_request(r#"{"name":"bing","age":3}"#))
+            .ok()
+            .unwrap();
+        assert_eq!(
+            user,
+            User {
+                name: "bing".to_string(),
+                age: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_json_rejection() {
+        let req = Request::new().body(r#"{"name":"bing","age":3}"#);
+        let rejection = Json::<User>::from_request(req).err().unwrap();
+        assert!(body(rejection.into_response()).starts_with("HTTP/1.1 415"));
+
+        let req = json_request(r#"{"name":"bing"}"#);
+        let rejection = Json::<User>::from_request(req).err().unwrap();
+        let resp = body(rejection.into_response());
+        assert!(resp.starts_with("HTTP/1.1 422 Unprocessable Entity"));
+        assert!(resp.contains("missing field `age`"));
+    }
+
+    #[test]
+    fn test_json_response() {
+        let resp = body(
+            Json(User {
+                name: "bing".to_string(),
+                age: 3,
+            })
+            .into_response(),
+        );
+        assert!(resp.contains("Content-Type: application/json\r\n"));
+        assert!(resp.ends_with("\r\n\r\n{\"name\":\"bing\",\"age\":3}"));
+    }
+}
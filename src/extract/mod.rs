@@ -0,0 +1,150 @@
+//! 提取器
+//!
+//! 处理函数的参数都通过提取器从请求中获取, 提取失败时直接返回对应的错误响应
+
+mod form;
+mod json;
+mod multipart;
+mod path;
+mod preconditions;
+mod query;
+mod rejection;
+mod state;
+
+pub use form::Form;
+pub use json::Json;
+pub use multipart::{Field, Multipart, MultipartLimits, TempFile};
+pub use path::Path;
+pub(crate) use path::PathParams;
+pub use preconditions::Preconditions;
+pub use query::Query;
+pub use rejection::Rejection;
+pub use state::State;
+
+use std::convert::Infallible;
+
+use crate::{
+    headers::{Accept, AcceptCharset, AcceptLanguage, Headers, HttpHeaders, HttpMethod},
+    request::Request,
+    response::IntoResponse,
+};
+
+mod private {
+    /// 从整个请求中提取
+    #[derive(Debug, Clone, Copy)]
+    pub enum ViaRequest {}
+
+    /// 从请求的头部信息中提取
+    #[derive(Debug, Clone, Copy)]
+    pub enum ViaParts {}
+}
+
+/// 只读取请求的头部信息(请求行、头部、扩展), 不消耗请求体
+///
+/// 可以作为处理函数除最后一个以外的任意参数
+pub trait FromRequestParts: Sized {
+    type Rejection: IntoResponse;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection>;
+}
+
+/// 消耗整个请求, 只能作为处理函数的最后一个参数
+pub trait FromRequest<M = private::ViaRequest>: Sized {
+    type Rejection: IntoResponse;
+
+    fn from_request(req: Request) -> Result<Self, Self::Rejection>;
+}
+
+/// 请求的媒体类型, 去掉参数并转为小写, 例如 `application/json`
+pub(crate) fn content_type(req: &Request) -> Option<String> {
+    let value = req.headers.get(&Headers::ContentType.to_string())?;
+    let mime = value.split(';').next().unwrap_or_default().trim();
+    Some(mime.to_ascii_lowercase())
+}
+
+impl<T: FromRequestParts> FromRequest<private::ViaParts> for T {
+    type Rejection = <T as FromRequestParts>::Rejection;
+
+    fn from_request(mut req: Request) -> Result<Self, Self::Rejection> {
+        T::from_request_parts(&mut req)
+    }
+}
+
+impl FromRequest for Request {
+    type Rejection = Rejection;
+
+    fn from_request(req: Request) -> Result<Self, Self::Rejection> {
+        Ok(req)
+    }
+}
+
+/// 原始请求体
+impl FromRequest for Vec<u8> {
+    type Rejection = Rejection;
+
+    fn from_request(mut req: Request) -> Result<Self, Self::Rejection> {
+        Ok(std::mem::take(&mut req.body))
+    }
+}
+
+/// UTF-8 请求体
+impl FromRequest for String {
+    type Rejection = Rejection;
+
+    fn from_request(req: Request) -> Result<Self, Self::Rejection> {
+        let body = Vec::<u8>::from_request(req)?;
+        String::from_utf8(body).map_err(|_| Rejection::InvalidUtf8)
+    }
+}
+
+impl FromRequestParts for HttpHeaders {
+    type Rejection = Rejection;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        Ok(req.headers.clone())
+    }
+}
+
+/// 没有 `Accept` 时接受任何类型
+impl FromRequestParts for Accept {
+    type Rejection = Infallible;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        let value = req.headers.get(&Headers::Accept.to_string());
+        Ok(value.map(Accept::parse).unwrap_or_default())
+    }
+}
+
+impl FromRequestParts for AcceptLanguage {
+    type Rejection = Infallible;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        let value = req.headers.get(&Headers::AcceptLanguage.to_string());
+        Ok(value.map(AcceptLanguage::parse).unwrap_or_default())
+    }
+}
+
+impl FromRequestParts for AcceptCharset {
+    type Rejection = Infallible;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        let value = req.headers.get(&Headers::AcceptCharset.to_string());
+        Ok(value.map(AcceptCharset::parse).unwrap_or_default())
+    }
+}
+
+impl FromRequestParts for HttpMethod {
+    type Rejection = Rejection;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        Ok(*req.method_ref())
+    }
+}
+
+impl<T: FromRequestParts> FromRequestParts for Option<T> {
+    type Rejection = Rejection;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        Ok(T::from_request_parts(req).ok())
+    }
+}
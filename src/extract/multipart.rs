@@ -0,0 +1,605 @@
+//! multipart 表单
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    error::MultipartError,
+    headers::{HttpHeaders, Mime, StatusCode},
+    request::{BodyReader, Request, find},
+    response::{IntoResponse, Response},
+};
+
+use super::{FromRequest, Rejection, State, content_type};
+
+/// 每次从连接中读取的字节数
+const CHUNK_SIZE: usize = 8 * 1024;
+/// 单个字段头部的最大长度
+const MAX_PART_HEADER_SIZE: usize = 8 * 1024;
+
+/// multipart 请求的大小限制
+///
+/// 通过 [`Router::with_state`](crate::Router::with_state) 设置后对所有
+/// [`Multipart`] 生效, 也可以在处理函数中调用 [`Multipart::set_limits`]
+#[derive(Debug, Clone, Copy)]
+pub struct MultipartLimits {
+    /// 单个字段内容的最大字节数
+    pub max_part_size: usize,
+    /// 整个请求体的最大字节数
+    pub max_total_size: usize,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        Self {
+            max_part_size: 16 * 1024 * 1024,
+            max_total_size: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// `multipart/form-data` 表单
+///
+/// 请求体不会一次读入内存, 通过 [`Multipart::next_field`] 逐个读取字段,
+/// 字段内容可以读到内存中, 也可以写入临时文件。
+/// `Content-Type` 不是 `multipart/form-data` 时返回 415, 缺少 boundary 时返回 400
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, error::MultipartError, extract::Multipart};
+///
+/// fn upload(mut multipart: Multipart) -> Result<String, MultipartError> {
+///     let mut names = Vec::new();
+///     while let Some(mut field) = multipart.next_field()? {
+///         if field.file_name().is_some() {
+///             let file = field.save_to_temp()?;
+///             names.push(format!("{}", file.path().display()));
+///         } else {
+///             names.push(field.text()?);
+///         }
+///     }
+///     Ok(names.join(","))
+/// }
+///
+/// let app = Router::new().route("/upload", "POST", upload);
+/// ```
+#[derive(Debug)]
+pub struct Multipart {
+    reader: BodyReader,
+    /// `\r\n--boundary`
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+    limits: MultipartLimits,
+    /// 已经从请求体读取的字节数
+    total: usize,
+    /// 当前字段已经读取的字节数
+    part: usize,
+    /// 已经跳过第一个分隔符之前的内容
+    started: bool,
+    in_part: bool,
+    eof: bool,
+    finished: bool,
+}
+
+impl FromRequest for Multipart {
+    type Rejection = Rejection;
+
+    fn from_request(mut req: Request) -> Result<Self, Self::Rejection> {
+        if content_type(&req).as_deref() != Some(Mime::MultipartFormData.to_string().as_str()) {
+            return Err(Rejection::MissingMultipartContentType);
+        }
+        let boundary = req
+            .headers
+            .get("Content-Type")
+            .and_then(boundary)
+            .ok_or(Rejection::MissingBoundary)?;
+        let limits = req
+            .extensions
+            .get::<State<MultipartLimits>>()
+            .map(|State(limits)| *limits)
+            .unwrap_or_default();
+        Ok(Self::new(req.take_body_reader(), &boundary, limits))
+    }
+}
+
+impl Multipart {
+    pub fn new(reader: BodyReader, boundary: &str, limits: MultipartLimits) -> Self {
+        Self {
+            reader,
+            delimiter: format!("\r\n--{boundary}").into_bytes(),
+            // 第一个分隔符前没有换行, 补上之后所有分隔符都是 `\r\n--boundary`
+            buf: b"\r\n".to_vec(),
+            limits,
+            total: 0,
+            part: 0,
+            started: false,
+            in_part: false,
+            eof: false,
+            finished: false,
+        }
+    }
+
+    /// 修改大小限制
+    pub fn set_limits(&mut self, limits: MultipartLimits) {
+        self.limits = limits;
+    }
+
+    /// 读取下一个字段, 上一个字段没有读完的内容会被丢弃
+    pub fn next_field(&mut self) -> Result<Option<Field<'_>>, MultipartError> {
+        if self.finished {
+            return Ok(None);
+        }
+        if self.in_part {
+            let mut sink = [0; CHUNK_SIZE];
+            while self.read_part(&mut sink)? > 0 {}
+        } else if !self.started {
+            self.skip_preamble()?;
+            self.started = true;
+        }
+
+        // 分隔符后是 `--` 表示结束
+        self.fill_until(|buf| buf.len() >= 2)?;
+        if self.buf.starts_with(b"--") {
+            self.finished = true;
+            return Ok(None);
+        }
+
+        let end = loop {
+            if let Some(pos) = find(&self.buf, b"\r\n\r\n") {
+                break pos;
+            }
+            if self.buf.len() > MAX_PART_HEADER_SIZE {
+                return Err(MultipartError::HeaderTooLarge);
+            }
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("字段头部不完整"));
+            }
+        };
+        let headers = parse_headers(&self.buf[..end])?;
+        self.buf.drain(..end + 4);
+        self.in_part = true;
+        self.part = 0;
+
+        let disposition = headers.get("Content-Disposition").unwrap_or_default();
+        Ok(Some(Field {
+            name: param(disposition, "name"),
+            file_name: param(disposition, "filename"),
+            content_type: headers.get("Content-Type").map(str::to_string),
+            headers,
+            multipart: self,
+        }))
+    }
+
+    /// 丢弃第一个分隔符之前的内容
+    fn skip_preamble(&mut self) -> Result<(), MultipartError> {
+        loop {
+            if let Some(pos) = find(&self.buf, &self.delimiter) {
+                self.buf.drain(..pos + self.delimiter.len());
+                return Ok(());
+            }
+            let keep = self.buf.len().min(self.delimiter.len() - 1);
+            self.buf.drain(..self.buf.len() - keep);
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("没有找到 boundary"));
+            }
+        }
+    }
+
+    /// 读取当前字段的内容, 遇到分隔符时返回 0
+    fn read_part(&mut self, out: &mut [u8]) -> Result<usize, MultipartError> {
+        if !self.in_part || out.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            // 缓冲区末尾可能是分隔符的前半部分, 不能当作内容返回
+            let available = match find(&self.buf, &self.delimiter) {
+                Some(0) => {
+                    self.buf.drain(..self.delimiter.len());
+                    self.in_part = false;
+                    return Ok(0);
+                }
+                Some(pos) => pos,
+                None => self.buf.len().saturating_sub(self.delimiter.len() - 1),
+            };
+            if available > 0 {
+                let n = available.min(out.len());
+                self.part += n;
+                if self.part > self.limits.max_part_size {
+                    return Err(MultipartError::PartTooLarge(self.limits.max_part_size));
+                }
+                out[..n].copy_from_slice(&self.buf[..n]);
+                self.buf.drain(..n);
+                return Ok(n);
+            }
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("字段内容不完整"));
+            }
+        }
+    }
+
+    fn fill_until(&mut self, f: impl Fn(&[u8]) -> bool) -> Result<(), MultipartError> {
+        while !f(&self.buf) {
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("请求体不完整"));
+            }
+        }
+        Ok(())
+    }
+
+    /// 从请求体读取一块数据, 请求体已经读完时返回 `false`
+    fn fill(&mut self) -> Result<bool, MultipartError> {
+        if self.eof {
+            return Ok(false);
+        }
+        let mut chunk = [0; CHUNK_SIZE];
+        let n = self.reader.read(&mut chunk)?;
+        if n == 0 {
+            self.eof = true;
+            return Ok(false);
+        }
+        self.total += n;
+        if self.total > self.limits.max_total_size {
+            return Err(MultipartError::BodyTooLarge(self.limits.max_total_size));
+        }
+        self.buf.extend_from_slice(&chunk[..n]);
+        Ok(true)
+    }
+}
+
+/// multipart 表单中的一个字段
+///
+/// 实现了 [`Read`], 读到字段末尾时返回 0
+#[derive(Debug)]
+pub struct Field<'a> {
+    multipart: &'a mut Multipart,
+    name: Option<String>,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    headers: HttpHeaders,
+}
+
+impl Field<'_> {
+    /// `Content-Disposition` 中的 `name`
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// `Content-Disposition` 中的 `filename`
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    pub fn headers(&self) -> &HttpHeaders {
+        &self.headers
+    }
+
+    /// 把剩余内容读到内存中
+    pub fn data(&mut self) -> Result<Vec<u8>, MultipartError> {
+        let mut data = Vec::new();
+        let mut chunk = [0; CHUNK_SIZE];
+        loop {
+            let n = self.multipart.read_part(&mut chunk)?;
+            if n == 0 {
+                return Ok(data);
+            }
+            data.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// 把剩余内容读为字符串
+    pub fn text(&mut self) -> Result<String, MultipartError> {
+        String::from_utf8(self.data()?).map_err(|_| MultipartError::InvalidUtf8)
+    }
+
+    /// 把剩余内容写入文件, 返回写入的字节数
+    pub fn save_to(&mut self, path: impl AsRef<Path>) -> Result<u64, MultipartError> {
+        let mut file = File::create(path)?;
+        self.copy_to(&mut file)
+    }
+
+    /// 把剩余内容写入临时文件
+    pub fn save_to_temp(&mut self) -> Result<TempFile, MultipartError> {
+        let file = TempFile::new()?;
+        self.save_to(file.path())?;
+        Ok(file)
+    }
+
+    fn copy_to(&mut self, writer: &mut impl Write) -> Result<u64, MultipartError> {
+        let mut written = 0;
+        let mut chunk = [0; CHUNK_SIZE];
+        loop {
+            let n = self.multipart.read_part(&mut chunk)?;
+            if n == 0 {
+                writer.flush()?;
+                return Ok(written);
+            }
+            writer.write_all(&chunk[..n])?;
+            written += n as u64;
+        }
+    }
+}
+
+impl Read for Field<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.multipart.read_part(buf).map_err(|e| match e {
+            MultipartError::Io(e) => e,
+            e => io::Error::other(e),
+        })
+    }
+}
+
+/// 临时文件, 离开作用域时删除
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl TempFile {
+    fn new() -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir();
+        loop {
+            let name = format!(
+                "http_sv-{}-{}.upload",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            );
+            let path = dir.join(name);
+            match File::options().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path, keep: false }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 把临时文件移动到 `path`, 之后不会再被删除
+    pub fn persist(mut self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = path.as_ref().to_path_buf();
+        if std::fs::rename(&self.path, &path).is_err() {
+            // 跨文件系统时无法重命名
+            std::fs::copy(&self.path, &path)?;
+            std::fs::remove_file(&self.path)?;
+        }
+        self.keep = true;
+        Ok(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl IntoResponse for MultipartError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Self::PartTooLarge(_) | Self::BodyTooLarge(_) => StatusCode::PayloadTooLarge,
+            Self::Io(_) => StatusCode::InternalServerError,
+            _ => StatusCode::BadRequest,
+        };
+        Response::new()
+            .status(status)
+            .body(self.to_string().into_bytes())
+    }
+}
+
+/// `Content-Type` 中的 boundary
+fn boundary(content_type: &str) -> Option<String> {
+    param(content_type, "boundary").filter(|boundary| !boundary.is_empty())
+}
+
+/// 读取 `a; key=value; key="value"` 形式的参数
+fn param(value: &str, key: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|part| {
+        let (k, v) = part.split_once('=')?;
+        if !k.trim().eq_ignore_ascii_case(key) {
+            return None;
+        }
+        let v = v.trim();
+        let v = match v.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+            None => v.to_string(),
+        };
+        Some(v)
+    })
+}
+
+/// 解析字段头部, 开头是分隔符后的 `\r\n`
+fn parse_headers(buf: &[u8]) -> Result<HttpHeaders, MultipartError> {
+    let text =
+        std::str::from_utf8(buf).map_err(|_| MultipartError::Malformed("字段头部不是 UTF-8"))?;
+    let mut headers = HttpHeaders::new();
+    for line in text.split("\r\n").filter(|line| !line.is_empty()) {
+        let (key, value) = line
+            .split_once(':')
+            .ok_or(MultipartError::Malformed("字段头部格式错误"))?;
+        headers
+            .0
+            .insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::{Multipart, MultipartLimits, boundary, param};
+    use crate::{
+        error::MultipartError,
+        extract::FromRequest,
+        headers::Headers,
+        request::{BodyReader, Request},
+        response::{IntoResponse, Response},
+    };
+
+    const BODY: &str = "preamble\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\
+        \r\n\
+        hello\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        line1\r\n--Xy\r\nline2\r\n\
+        --XyZ--\r\n";
+
+    /// 每次只返回一个字节, 模拟分多次到达的请求体
+    struct Slow(Vec<u8>, usize);
+
+    impl Read for Slow {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.1 >= self.0.len() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0[self.1];
+            self.1 += 1;
+            Ok(1)
+        }
+    }
+
+    fn new_multipart(body: &str, limits: MultipartLimits) -> Multipart {
+        let reader = BodyReader::new(Vec::new(), Slow(body.as_bytes().to_vec(), 0), body.len());
+        Multipart::new(reader, "XyZ", limits)
+    }
+
+    fn status(resp: Response) -> String {
+        let buf: Vec<u8> = resp.into();
+        String::from_utf8_lossy(&buf[..12]).to_string()
+    }
+
+    #[test]
+    fn test_param() {
+        let value = "form-data; name=\"file\"; filename=\"a \\\"b\\\".txt\"";
+        assert_eq!(param(value, "name").as_deref(), Some("file"));
+        assert_eq!(param(value, "filename").as_deref(), Some("a \"b\".txt"));
+        assert_eq!(
+            boundary("multipart/form-data; boundary=XyZ").as_deref(),
+            Some("XyZ")
+        );
+        assert_eq!(boundary("multipart/form-data"), None);
+    }
+
+    #[test]
+    fn test_fields() {
+        let mut multipart = new_multipart(BODY, MultipartLimits::default());
+
+        let mut field = multipart.next_field().unwrap().unwrap();
+        assert_eq!(field.name(), Some("title"));
+        assert_eq!(field.file_name(), None);
+        assert_eq!(field.content_type(), None);
+        assert_eq!(field.headers().0.len(), 1);
+        assert_eq!(field.text().unwrap(), "hello");
+
+        let mut field = multipart.next_field().unwrap().unwrap();
+        assert_eq!(field.name(), Some("file"));
+        assert_eq!(field.file_name(), Some("a.txt"));
+        assert_eq!(field.content_type(), Some("text/plain"));
+        let file = field.save_to_temp().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            "line1\r\n--Xy\r\nline2"
+        );
+        let path = file.path().to_path_buf();
+        drop(file);
+        assert!(!path.exists());
+
+        assert!(multipart.next_field().unwrap().is_none());
+        assert!(multipart.next_field().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_skip_unread_field() {
+        let mut multipart = new_multipart(BODY, MultipartLimits::default());
+        let mut field = multipart.next_field().unwrap().unwrap();
+        let mut buf = [0; 2];
+        field.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"he");
+
+        let field = multipart.next_field().unwrap().unwrap();
+        assert_eq!(field.name(), Some("file"));
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = MultipartLimits {
+            max_part_size: 8,
+            ..Default::default()
+        };
+        let mut multipart = new_multipart(BODY, limits);
+        multipart.next_field().unwrap().unwrap().data().unwrap();
+        let err = multipart.next_field().unwrap().unwrap().data().unwrap_err();
+        assert!(matches!(err, MultipartError::PartTooLarge(8)));
+        assert_eq!(status(err.into_response()), "HTTP/1.1 413");
+
+        let limits = MultipartLimits {
+            max_total_size: 64,
+            ..Default::default()
+        };
+        let mut multipart = new_multipart(BODY, limits);
+        let err = loop {
+            match multipart.next_field() {
+                Ok(Some(mut field)) => {
+                    if let Err(e) = field.data() {
+                        break e;
+                    }
+                }
+                Ok(None) => panic!("超过限制时应该返回错误"),
+                Err(e) => break e,
+            }
+        };
+        assert!(matches!(err, MultipartError::BodyTooLarge(64)));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let mut multipart = new_multipart(
+            "--XyZ\r\nContent-Disposition: form-data\r\n\r\nabc",
+            Default::default(),
+        );
+        let mut field = multipart.next_field().unwrap().unwrap();
+        assert!(matches!(field.data(), Err(MultipartError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_extract() {
+        let mut req = Request::new().method("POST").headers(
+            Headers::ContentType,
+            "multipart/form-data; boundary=\"XyZ\"",
+        );
+        req.body = BODY.as_bytes().to_vec();
+        let mut multipart = Multipart::from_request(req).unwrap();
+        assert_eq!(
+            multipart.next_field().unwrap().unwrap().text().unwrap(),
+            "hello"
+        );
+
+        let req = Request::new().method("POST").body(BODY);
+        let rejection = Multipart::from_request(req).unwrap_err();
+        assert_eq!(status(rejection.into_response()), "HTTP/1.1 415");
+
+        let req = Request::new()
+            .method("POST")
+            .headers(Headers::ContentType, "multipart/form-data");
+        let rejection = Multipart::from_request(req).unwrap_err();
+        assert_eq!(status(rejection.into_response()), "HTTP/1.1 400");
+    }
+}
@@ -0,0 +1,252 @@
+//! 路径参数提取
+
+use serde::{
+    Deserializer,
+    de::{
+        self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+
+use crate::{error::UrlEncodedError, request::Request, utils::urlencoded::PartDeserializer};
+
+use super::{FromRequestParts, Rejection};
+
+/// 路由匹配到的路径参数, 由路由写入请求扩展
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PathParams(pub Vec<(String, String)>);
+
+/// 把路由中的 `:name` 和 `*name` 参数反序列化为 `T`
+///
+/// 只有一个参数时 `T` 可以是单个值, 多个参数时使用元组(按顺序)或结构体(按名称)
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, extract::Path};
+///
+/// fn user(Path(id): Path<u32>) -> String {
+///     format!("user {id}")
+/// }
+///
+/// fn post(Path((user, post)): Path<(String, u32)>) -> String {
+///     format!("{user}: {post}")
+/// }
+///
+/// let app = Router::new()
+///     .route("/users/:id", "GET", user)
+///     .route("/users/:user/posts/:post", "GET", post);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Path<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequestParts for Path<T> {
+    type Rejection = Rejection;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        let params = req
+            .extensions
+            .get::<PathParams>()
+            .ok_or(Rejection::MissingPathParams)?;
+        T::deserialize(PathDeserializer(&params.0))
+            .map(Path)
+            .map_err(Rejection::InvalidPathParams)
+    }
+}
+
+struct PathDeserializer<'a>(&'a [(String, String)]);
+
+impl PathDeserializer<'_> {
+    /// 目标是单个值时, 必须正好有一个参数
+    fn single(&self) -> Result<PartDeserializer<'_>, UrlEncodedError> {
+        match self.0 {
+            [(_, value)] => Ok(PartDeserializer(value)),
+            _ => Err(de::Error::custom(format_args!(
+                "需要 1 个路径参数, 实际有 {} 个",
+                self.0.len()
+            ))),
+        }
+    }
+}
+
+macro_rules! deserialize_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for PathDeserializer<'_> {
+    type Error = UrlEncodedError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(PathAccess {
+            iter: self.0.iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(PathAccess {
+            iter: self.0.iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if len != self.0.len() {
+            return Err(de::Error::invalid_length(self.0.len(), &visitor));
+        }
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    deserialize_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_option
+    }
+
+    forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct identifier ignored_any
+    }
+}
+
+struct PathAccess<'a> {
+    iter: std::slice::Iter<'a, (String, String)>,
+    value: Option<&'a str>,
+}
+
+impl<'de> MapAccess<'de> for PathAccess<'_> {
+    type Error = UrlEncodedError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.as_str().into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = self.value.take().unwrap_or_default();
+        seed.deserialize(PartDeserializer(value))
+    }
+}
+
+impl<'de> SeqAccess<'de> for PathAccess<'_> {
+    type Error = UrlEncodedError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((_, value)) => seed.deserialize(PartDeserializer(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::{Path, PathParams};
+    use crate::{extract::FromRequestParts, request::Request};
+
+    fn request(params: &[(&str, &str)]) -> Request {
+        let params = params
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Request::new().extension(PathParams(params))
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Params {
+        user: String,
+        post: u32,
+    }
+
+    #[test]
+    fn test_path() {
+        let mut req = request(&[("id", "42")]);
+        let Path(id) = Path::<u32>::from_request_parts(&mut req).unwrap();
+        assert_eq!(id, 42);
+
+        let mut req = request(&[("user", "bing"), ("post", "7")]);
+        let Path(tuple) = Path::<(String, u32)>::from_request_parts(&mut req).unwrap();
+        assert_eq!(tuple, ("bing".to_string(), 7));
+        let Path(params) = Path::<Params>::from_request_parts(&mut req).unwrap();
+        assert_eq!(
+            params,
+            Params {
+                user: "bing".to_string(),
+                post: 7
+            }
+        );
+    }
+
+    #[test]
+    fn test_path_rejection() {
+        assert!(Path::<u32>::from_request_parts(&mut Request::new()).is_err());
+        assert!(Path::<u32>::from_request_parts(&mut request(&[("id", "x")])).is_err());
+        let mut req = request(&[("a", "1"), ("b", "2")]);
+        assert!(Path::<u32>::from_request_parts(&mut req).is_err());
+        assert!(Path::<(u32, u32, u32)>::from_request_parts(&mut req).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! 条件请求
+
+use std::convert::Infallible;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    headers::{EntityTag, Headers, HttpMethod, StatusCode},
+    request::Request,
+    response::Response,
+    utils::date,
+};
+
+use super::FromRequestParts;
+
+/// 请求中的条件头部: `If-Match`、`If-None-Match`、`If-Modified-Since` 和 `If-Unmodified-Since`
+///
+/// [`ConditionalLayer`](crate::middleware::ConditionalLayer) 在处理函数返回后检查 `GET` 和 `HEAD`,
+/// 修改资源的处理函数需要在修改前自己调用 [`check`](Self::check)
+///
+/// # Example
+/// ```rust
+/// use http_sv::{
+///     extract::Preconditions,
+///     headers::{EntityTag, HttpMethod, StatusCode},
+/// };
+///
+/// fn update(method: HttpMethod, preconditions: Preconditions) -> StatusCode {
+///     let current = EntityTag::strong("v2");
+///     if let Err(status) = preconditions.check(&method, Some(&current), None) {
+///         return status;
+///     }
+///     // 修改资源
+///     StatusCode::OK
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Preconditions {
+    if_match: Option<String>,
+    if_none_match: Option<String>,
+    if_modified_since: Option<String>,
+    if_unmodified_since: Option<String>,
+}
+
+impl Preconditions {
+    pub(crate) fn from_request(req: &Request) -> Self {
+        let get = |key: Headers| req.headers.get(&key.to_string()).map(str::to_string);
+        Self {
+            if_match: get(Headers::IfMatch),
+            if_none_match: get(Headers::IfNoneMatch),
+            if_modified_since: get(Headers::IfModifiedSince),
+            if_unmodified_since: get(Headers::IfUnmodifiedSince),
+        }
+    }
+
+    /// 没有任何条件头部
+    pub fn is_empty(&self) -> bool {
+        self.if_match.is_none()
+            && self.if_none_match.is_none()
+            && self.if_modified_since.is_none()
+            && self.if_unmodified_since.is_none()
+    }
+
+    /// 按 RFC 9110 第 13.2.2 节的顺序检查条件
+    ///
+    /// `etag` 和 `last_modified` 是资源当前的验证器, 资源不存在时都传 `None`。
+    /// 条件不满足时返回 304 或 412
+    pub fn check(
+        &self,
+        method: &HttpMethod,
+        etag: Option<&EntityTag>,
+        last_modified: Option<DateTime<Utc>>,
+    ) -> Result<(), StatusCode> {
+        if let Some(if_match) = &self.if_match {
+            if !matches_any(if_match, etag, EntityTag::strong_eq) {
+                return Err(StatusCode::PreconditionFailed);
+            }
+        } else if let Some(since) = self.if_unmodified_since.as_deref().and_then(date::parse)
+            && last_modified.is_some_and(|last_modified| last_modified > since)
+        {
+            return Err(StatusCode::PreconditionFailed);
+        }
+
+        let safe = matches!(method, HttpMethod::GET | HttpMethod::HEAD);
+        if let Some(if_none_match) = &self.if_none_match {
+            if matches_any(if_none_match, etag, EntityTag::weak_eq) {
+                return Err(match safe {
+                    true => StatusCode::NotModified,
+                    false => StatusCode::PreconditionFailed,
+                });
+            }
+        } else if let Some(since) = self.if_modified_since.as_deref().and_then(date::parse)
+            && safe
+            && last_modified.is_some_and(|last_modified| last_modified <= since)
+        {
+            return Err(StatusCode::NotModified);
+        }
+        Ok(())
+    }
+
+    /// 用响应中的 `ETag` 和 `Last-Modified` 检查条件, 不满足时替换为 304 或 412
+    pub(crate) fn apply(&self, method: &HttpMethod, resp: Response) -> Response {
+        if self.is_empty() {
+            return resp;
+        }
+        let headers = resp.headers_ref();
+        let etag = headers
+            .get(&Headers::ETag.to_string())
+            .and_then(EntityTag::parse);
+        let last_modified = headers
+            .get(&Headers::LastModified.to_string())
+            .and_then(date::parse);
+        match self.check(method, etag.as_ref(), last_modified) {
+            Ok(()) => resp,
+            // 304 保留验证器等头部, 去掉响应体
+            Err(StatusCode::NotModified) => resp
+                .status(StatusCode::NotModified)
+                .raw_body(Vec::new())
+                .remove_header(Headers::ContentLength)
+                .remove_header(Headers::ContentType),
+            Err(status) => Response::new().status(status).raw_body(Vec::new()),
+        }
+    }
+}
+
+/// `*` 匹配任何存在的资源
+fn matches_any(
+    value: &str,
+    etag: Option<&EntityTag>,
+    eq: fn(&EntityTag, &EntityTag) -> bool,
+) -> bool {
+    let Some(etag) = etag else {
+        return false;
+    };
+    value.trim() == "*" || EntityTag::parse_list(value).iter().any(|tag| eq(tag, etag))
+}
+
+impl FromRequestParts for Preconditions {
+    type Rejection = Infallible;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_request(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Preconditions;
+    use crate::{
+        headers::{EntityTag, Headers, HttpMethod, StatusCode},
+        request::Request,
+        utils::date,
+    };
+
+    fn check(
+        key: Headers,
+        value: &str,
+        method: HttpMethod,
+        etag: Option<&str>,
+    ) -> Result<(), StatusCode> {
+        let req = Request::new().headers(key, value);
+        let etag = etag.and_then(EntityTag::parse);
+        let last_modified = date::parse("Wed, 21 Oct 2015 07:28:00 GMT");
+        Preconditions::from_request(&req).check(&method, etag.as_ref(), last_modified)
+    }
+
+    #[test]
+    fn test_check() {
+        use HttpMethod::{GET, PUT};
+
+        let not_modified = Err(StatusCode::NotModified);
+        let failed = Err(StatusCode::PreconditionFailed);
+        assert_eq!(
+            check(Headers::IfNoneMatch, "\"a\"", GET, Some("W/\"a\"")),
+            not_modified
+        );
+        assert_eq!(
+            check(Headers::IfNoneMatch, "\"b\", \"c\"", GET, Some("\"a\"")),
+            Ok(())
+        );
+        assert_eq!(
+            check(Headers::IfNoneMatch, "*", GET, Some("\"a\"")),
+            not_modified
+        );
+        assert_eq!(check(Headers::IfNoneMatch, "*", PUT, None), Ok(()));
+        assert_eq!(
+            check(Headers::IfNoneMatch, "\"a\"", PUT, Some("\"a\"")),
+            failed
+        );
+
+        assert_eq!(check(Headers::IfMatch, "\"a\"", PUT, Some("\"a\"")), Ok(()));
+        assert_eq!(
+            check(Headers::IfMatch, "\"a\"", PUT, Some("W/\"a\"")),
+            failed
+        );
+        assert_eq!(check(Headers::IfMatch, "*", PUT, None), failed);
+
+        let before = "Tue, 20 Oct 2015 07:28:00 GMT";
+        let same = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert_eq!(
+            check(Headers::IfModifiedSince, same, GET, None),
+            not_modified
+        );
+        assert_eq!(check(Headers::IfModifiedSince, before, GET, None), Ok(()));
+        assert_eq!(check(Headers::IfModifiedSince, same, PUT, None), Ok(()));
+        assert_eq!(check(Headers::IfModifiedSince, "bad", GET, None), Ok(()));
+        assert_eq!(check(Headers::IfUnmodifiedSince, before, PUT, None), failed);
+        assert_eq!(check(Headers::IfUnmodifiedSince, same, PUT, None), Ok(()));
+
+        // If-None-Match 存在时忽略 If-Modified-Since
+        let req = Request::new()
+            .headers(Headers::IfNoneMatch, "\"b\"")
+            .headers(Headers::IfModifiedSince, same);
+        let etag = EntityTag::strong("a");
+        let result = Preconditions::from_request(&req).check(&GET, Some(&etag), date::parse(same));
+        assert_eq!(result, Ok(()));
+    }
+}
@@ -0,0 +1,35 @@
+//! 查询参数提取
+
+use serde::de::DeserializeOwned;
+
+use crate::request::Request;
+
+use super::{FromRequestParts, Rejection};
+
+/// 把查询字符串反序列化为 `T`
+///
+/// # Example
+/// ```rust
+/// use http_sv::extract::Query;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Search {
+///     q: String,
+///     tag: Vec<String>,
+/// }
+///
+/// fn search(Query(search): Query<Search>) -> String {
+///     format!("{} {:?}", search.q, search.tag)
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Query<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequestParts for Query<T> {
+    type Rejection = Rejection;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        req.query().map(Query).map_err(Rejection::InvalidQuery)
+    }
+}
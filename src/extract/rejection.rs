@@ -0,0 +1,77 @@
+//! 提取失败
+
+use thiserror::Error;
+
+use crate::{
+    error::UrlEncodedError,
+    headers::{Headers, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// 内置提取器的错误
+///
+/// 服务端配置错误为 500, 请求体类型错误为 415, 请求体内容错误为 422, 其他为 400
+#[derive(Debug, Error)]
+pub enum Rejection {
+    #[error("路由没有设置 `{0}` 类型的状态")]
+    MissingState(&'static str),
+    #[error("路由没有添加 `SessionLayer`")]
+    MissingSession,
+    #[error("路由中没有路径参数")]
+    MissingPathParams,
+    #[error("路径参数错误--> {0}")]
+    InvalidPathParams(UrlEncodedError),
+    #[error("查询参数错误--> {0}")]
+    InvalidQuery(UrlEncodedError),
+    #[error("请求的 Content-Type 必须是 `application/json`")]
+    MissingJsonContentType,
+    #[error("JSON 解析失败--> {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("请求的 Content-Type 必须是 `application/x-www-form-urlencoded`")]
+    MissingFormContentType,
+    #[error("表单解析失败--> {0}")]
+    InvalidForm(UrlEncodedError),
+    #[error("请求的 Content-Type 必须是 `multipart/form-data`")]
+    MissingMultipartContentType,
+    #[error("multipart 请求缺少 boundary")]
+    MissingBoundary,
+    #[error("请求体不是合法的 UTF-8")]
+    InvalidUtf8,
+    #[error("没有客户端可以接受的表示")]
+    NotAcceptable,
+    #[error("WebSocket 握手错误--> {0}")]
+    InvalidWebSocketUpgrade(&'static str),
+    #[error("只支持 WebSocket 版本 13")]
+    UnsupportedWebSocketVersion,
+}
+
+impl Rejection {
+    /// 作为响应返回时的状态码
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::MissingState(_) | Self::MissingSession | Self::MissingPathParams => {
+                StatusCode::InternalServerError
+            }
+            Self::MissingJsonContentType
+            | Self::MissingFormContentType
+            | Self::MissingMultipartContentType => StatusCode::UnsupportedMediaType,
+            Self::InvalidJson(_) | Self::InvalidForm(_) => StatusCode::UnprocessableEntity,
+            Self::NotAcceptable => StatusCode::NotAcceptable,
+            Self::UnsupportedWebSocketVersion => StatusCode::UpgradeRequired,
+            _ => StatusCode::BadRequest,
+        }
+    }
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        let resp = Response::new()
+            .status(self.status())
+            .body(self.to_string().into_bytes());
+        match self {
+            // 告诉客户端支持的版本
+            Self::UnsupportedWebSocketVersion => resp.header(Headers::SecWebSocketVersion, "13"),
+            _ => resp,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! 共享状态
+
+use crate::request::Request;
+
+use super::{FromRequestParts, Rejection};
+
+/// 通过 [`Router::with_state`](crate::Router::with_state) 设置的共享状态
+///
+/// 每个请求都会得到状态的一个克隆, 需要共享修改时使用 `Arc<Mutex<_>>` 或原子类型
+///
+/// # Example
+/// ```rust
+/// use std::sync::{
+///     Arc,
+///     atomic::{AtomicUsize, Ordering},
+/// };
+///
+/// use http_sv::{Router, extract::State};
+///
+/// #[derive(Clone, Default)]
+/// struct AppState {
+///     visits: Arc<AtomicUsize>,
+/// }
+///
+/// fn visit(State(state): State<AppState>) -> String {
+///     let n = state.visits.fetch_add(1, Ordering::Relaxed) + 1;
+///     format!("visit {n}")
+/// }
+///
+/// let app = Router::new()
+///     .route("/", "GET", visit)
+///     .with_state(AppState::default());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct State<T>(pub T);
+
+impl<T> FromRequestParts for State<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Rejection = Rejection;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        req.extensions
+            .get::<State<T>>()
+            .cloned()
+            .ok_or(Rejection::MissingState(std::any::type_name::<T>()))
+    }
+}
@@ -1,7 +1,213 @@
 //! 处理请求
 
-pub(crate) trait _Handle<Request> {
-    type Response;
-    type Error;
-    fn handel_request(&mut self, request: Request) -> Result<Self::Response, Self::Error>;
+use std::marker::PhantomData;
+
+use crate::{
+    error::ResponseError,
+    extract::{FromRequest, FromRequestParts},
+    request::Request,
+    response::{IntoResponse, Response},
+    server::Service,
+};
+
+/// 可以注册到路由上的处理器
+///
+/// `T` 只用于区分不同的实现:
+/// - 实现了 [`Service`] 的类型, 例如 `String`、`&str` 或自定义的有状态服务
+/// - 参数都是提取器的函数, 最多 12 个参数, 除最后一个外都必须实现 [`FromRequestParts`]
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, extract::{Path, Query}, headers::HttpMethod};
+/// use std::collections::HashMap;
+///
+/// fn handler(method: HttpMethod, Path(id): Path<u32>, Query(q): Query<HashMap<String, String>>) -> String {
+///     format!("{method} {id} {q:?}")
+/// }
+///
+/// let app = Router::new().route("/items/:id", "GET", handler);
+/// ```
+///
+/// 处理器需要实现 `Clone + Send`, 见 [`Service`] 中关于并发的说明
+pub trait Handler<T>: Clone + Send + 'static {
+    fn call(&mut self, req: Request) -> Response;
+}
+
+/// 通过 [`Service`] 实现的处理器
+#[derive(Debug, Clone, Copy)]
+pub enum IntoServiceMarker {}
+
+impl<S> Handler<IntoServiceMarker> for S
+where
+    S: Service<Request> + Clone + Send + 'static,
+    S::Response: IntoResponse,
+    S::Error: IntoResponse,
+{
+    fn call(&mut self, req: Request) -> Response {
+        match Service::call(self, req) {
+            Ok(resp) => resp.into_response(),
+            Err(e) => e.into_response(),
+        }
+    }
+}
+
+impl<F, R> Handler<()> for F
+where
+    F: Fn() -> R + Clone + Send + 'static,
+    R: IntoResponse,
+{
+    fn call(&mut self, req: Request) -> Response {
+        let _ = req;
+        (self)().into_response()
+    }
+}
+
+macro_rules! impl_handler {
+    ($($ty:ident),* ; $last:ident) => {
+        #[allow(non_snake_case)]
+        impl<F, R, M, $($ty,)* $last> Handler<(M, $($ty,)* $last,)> for F
+        where
+            F: Fn($($ty,)* $last) -> R + Clone + Send + 'static,
+            R: IntoResponse,
+            $($ty: FromRequestParts,)*
+            $last: FromRequest<M>,
+        {
+            #[allow(unused_mut)]
+            fn call(&mut self, mut req: Request) -> Response {
+                $(
+                    let $ty = match $ty::from_request_parts(&mut req) {
+                        Ok(value) => value,
+                        Err(rejection) => return rejection.into_response(),
+                    };
+                )*
+                let $last = match $last::from_request(req) {
+                    Ok(value) => value,
+                    Err(rejection) => return rejection.into_response(),
+                };
+                (self)($($ty,)* $last).into_response()
+            }
+        }
+    };
+}
+
+impl_handler!(; T1);
+impl_handler!(T1; T2);
+impl_handler!(T1, T2; T3);
+impl_handler!(T1, T2, T3; T4);
+impl_handler!(T1, T2, T3, T4; T5);
+impl_handler!(T1, T2, T3, T4, T5; T6);
+impl_handler!(T1, T2, T3, T4, T5, T6; T7);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7; T8);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8; T9);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9; T10);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10; T11);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11; T12);
+
+/// 把 [`Handler`] 包装为 [`Service`], 路由中存储的都是这个类型
+pub struct HandlerService<H, T> {
+    handler: H,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<H: Clone, T> Clone for HandlerService<H, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.handler.clone())
+    }
+}
+
+impl<H, T> HandlerService<H, T> {
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<H, T> Service<Request> for HandlerService<H, T>
+where
+    H: Handler<T>,
+{
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        Ok(self.handler.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::Handler;
+    use crate::{
+        extract::{Json, Query},
+        headers::{Headers, HttpHeaders, HttpMethod},
+        request::Request,
+        response::Response,
+    };
+
+    fn body(resp: Response) -> String {
+        let buf: Vec<u8> = resp.into();
+        String::from_utf8_lossy(&buf).to_string()
+    }
+
+    #[derive(Deserialize)]
+    struct Page {
+        page: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct User {
+        name: String,
+    }
+
+    fn create(method: HttpMethod, Query(page): Query<Page>, Json(user): Json<User>) -> String {
+        format!("{method} {} {}", page.page, user.name)
+    }
+
+    #[test]
+    fn test_extractor_handler() {
+        let mut handler = create;
+        let req = Request::new()
+            .method("POST")
+            .path("/users?page=2")
+            .unwrap()
+            .headers(Headers::ContentType, "application/json")
+            .body(r#"{"name": "bing"}"#);
+        let resp = body(Handler::call(&mut handler, req));
+        assert!(resp.ends_with("\r\n\r\nPOST 2 bing"));
+    }
+
+    #[test]
+    fn test_extractor_rejection() {
+        let mut handler = create;
+        let req = Request::new().path("/users?page=x").unwrap().body("{}");
+        let resp = body(Handler::call(&mut handler, req));
+        assert!(resp.starts_with("HTTP/1.1 400 Bad Request"));
+
+        let req = Request::new()
+            .path("/users?page=1")
+            .unwrap()
+            .headers(Headers::ContentType, "application/json")
+            .body("not json");
+        let resp = body(Handler::call(&mut handler, req));
+        assert!(resp.starts_with("HTTP/1.1 422 Unprocessable Entity"));
+    }
+
+    #[test]
+    fn test_raw_handlers() {
+        let mut handler = |headers: HttpHeaders, body: String| {
+            format!("{} {body}", headers.get("x-token").unwrap_or_default())
+        };
+        let mut req = Request::new().body("hi");
+        req.headers.notify("X-Token", "abc");
+        let resp = body(Handler::call(&mut handler, req));
+        assert!(resp.ends_with("\r\n\r\nabc hi\r\n"));
+
+        let mut handler = || "no args";
+        let resp = body(Handler::call(&mut handler, Request::new()));
+        assert!(resp.ends_with("\r\n\r\nno args"));
+    }
 }
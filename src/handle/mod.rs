@@ -1 +1,3 @@
 mod handle;
+
+pub use handle::{Handler, HandlerService, IntoServiceMarker};
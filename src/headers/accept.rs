@@ -0,0 +1,272 @@
+//! 内容协商
+//!
+//! 解析 `Accept`、`Accept-Language` 和 `Accept-Charset`, 从服务端支持的列表中选出客户端最想要的一项
+
+use super::Mime;
+
+/// 带权重的一项, 例如 `text/html;q=0.8`
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityItem {
+    pub value: String,
+    /// 0 到 1, 0 表示不可接受
+    pub quality: f32,
+}
+
+/// 解析逗号分隔的带权重列表, 权重格式错误的项被忽略
+///
+/// 除 `q` 以外的参数被丢弃
+pub fn parse_quality_list(value: &str) -> Vec<QualityItem> {
+    let mut items = Vec::new();
+    for item in value.split(',') {
+        let mut parts = item.split(';');
+        let value = parts.next().unwrap_or_default().trim();
+        if value.is_empty() {
+            continue;
+        }
+        let mut quality = Some(1.0);
+        for param in parts {
+            if let Some((key, q)) = param.split_once('=')
+                && key.trim().eq_ignore_ascii_case("q")
+            {
+                quality = q
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|q| (0.0..=1.0).contains(q));
+            }
+        }
+        if let Some(quality) = quality {
+            items.push(QualityItem {
+                value: value.to_ascii_lowercase(),
+                quality,
+            });
+        }
+    }
+    items
+}
+
+/// 从 `available` 中选出权重最高的一项, 权重相同时取靠前的
+///
+/// `specificity` 返回范围匹配的精确程度, 不匹配时返回 `None`, 使用最精确的范围的权重
+fn best<T: Copy>(
+    ranges: &[QualityItem],
+    available: &[T],
+    specificity: impl Fn(&str, T) -> Option<u8>,
+) -> Option<T> {
+    let mut best: Option<(T, f32)> = None;
+    for &candidate in available {
+        let quality = ranges
+            .iter()
+            .filter_map(|range| Some((specificity(&range.value, candidate)?, range.quality)))
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map_or(0.0, |(_, quality)| quality);
+        if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+            best = Some((candidate, quality));
+        }
+    }
+    best.map(|(candidate, _)| candidate)
+}
+
+/// `Accept` 请求头
+///
+/// 没有这个头部时接受任何类型。媒体类型的参数被忽略。
+/// 可以作为提取器使用, 响应随 `Accept` 变化时应当设置 `Vary: Accept`
+///
+/// # Example
+/// ```rust
+/// use http_sv::{
+///     Router,
+///     extract::Rejection,
+///     headers::{Accept, Headers, Mime},
+///     response::Response,
+/// };
+///
+/// fn report(accept: Accept) -> Result<Response, Rejection> {
+///     let mime = accept
+///         .negotiate(&[Mime::ApplicationJson, Mime::TextCsv])
+///         .ok_or(Rejection::NotAcceptable)?;
+///     let body = match mime {
+///         Mime::TextCsv => "id,name\n1,bing\n",
+///         _ => r#"[{"id":1,"name":"bing"}]"#,
+///     };
+///     Ok(Response::new()
+///         .header(Headers::ContentType, &mime.to_string())
+///         .header(Headers::Vary, "Accept")
+///         .body(body.into()))
+/// }
+///
+/// let app = Router::new().route("/report", "GET", report);
+///
+/// let accept = Accept::parse("text/html, application/*;q=0.9, */*;q=0.1");
+/// let available = [Mime::ApplicationJson, Mime::TextHtml];
+/// assert_eq!(accept.negotiate(&available), Some(Mime::TextHtml));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Accept(Option<Vec<QualityItem>>);
+
+impl Accept {
+    pub fn parse(value: &str) -> Self {
+        Self(Some(parse_quality_list(value)))
+    }
+
+    pub fn items(&self) -> &[QualityItem] {
+        self.0.as_deref().unwrap_or_default()
+    }
+
+    /// 选出最合适的类型, 都不可接受时返回 `None`, 应当响应 406
+    pub fn negotiate(&self, available: &[Mime]) -> Option<Mime> {
+        let Some(ranges) = &self.0 else {
+            return available.first().copied();
+        };
+        best(ranges, available, |range, mime| {
+            let mime = mime.to_string();
+            let (kind, _) = mime.split_once('/')?;
+            match range.split_once('/')? {
+                ("*", "*") => Some(0),
+                (range_kind, "*") if range_kind == kind => Some(1),
+                _ if range == mime => Some(2),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// `Accept-Language` 请求头
+///
+/// 按 RFC 4647 的基本过滤匹配, `en` 匹配 `en-US`, 没有这个头部时接受任何语言
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AcceptLanguage(Option<Vec<QualityItem>>);
+
+impl AcceptLanguage {
+    pub fn parse(value: &str) -> Self {
+        Self(Some(parse_quality_list(value)))
+    }
+
+    pub fn items(&self) -> &[QualityItem] {
+        self.0.as_deref().unwrap_or_default()
+    }
+
+    /// 选出最合适的语言标签, 都不可接受时返回 `None`
+    pub fn negotiate<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        let Some(ranges) = &self.0 else {
+            return available.first().copied();
+        };
+        best(ranges, available, |range, tag| {
+            if range == "*" {
+                return Some(0);
+            }
+            let tag = tag.to_ascii_lowercase();
+            let matched = tag == range
+                || tag
+                    .strip_prefix(range)
+                    .is_some_and(|rest| rest.starts_with('-'));
+            // 子标签越多越精确
+            matched.then(|| range.split('-').count() as u8)
+        })
+    }
+}
+
+/// `Accept-Charset` 请求头
+///
+/// 字符集名称不区分大小写, 没有这个头部时接受任何字符集
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AcceptCharset(Option<Vec<QualityItem>>);
+
+impl AcceptCharset {
+    pub fn parse(value: &str) -> Self {
+        Self(Some(parse_quality_list(value)))
+    }
+
+    pub fn items(&self) -> &[QualityItem] {
+        self.0.as_deref().unwrap_or_default()
+    }
+
+    /// 选出最合适的字符集, 都不可接受时返回 `None`
+    pub fn negotiate<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        let Some(ranges) = &self.0 else {
+            return available.first().copied();
+        };
+        best(ranges, available, |range, charset| match range {
+            "*" => Some(0),
+            _ if range.eq_ignore_ascii_case(charset) => Some(1),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Accept, AcceptCharset, AcceptLanguage, QualityItem, parse_quality_list};
+    use crate::headers::Mime;
+
+    #[test]
+    fn test_parse() {
+        let items = parse_quality_list("text/html;level=1, Text/CSV;q=0.5, bad;q=x, ,*/*;q=0");
+        assert_eq!(
+            items,
+            vec![
+                QualityItem {
+                    value: "text/html".to_string(),
+                    quality: 1.0
+                },
+                QualityItem {
+                    value: "text/csv".to_string(),
+                    quality: 0.5
+                },
+                QualityItem {
+                    value: "*/*".to_string(),
+                    quality: 0.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_accept() {
+        let available = [Mime::ApplicationJson, Mime::TextHtml, Mime::TextCsv];
+        let negotiate = |value: &str| Accept::parse(value).negotiate(&available);
+
+        assert_eq!(
+            Accept::default().negotiate(&available),
+            Some(Mime::ApplicationJson)
+        );
+        assert_eq!(negotiate("text/csv"), Some(Mime::TextCsv));
+        assert_eq!(
+            negotiate("text/*, application/json;q=0.5"),
+            Some(Mime::TextHtml)
+        );
+        assert_eq!(negotiate("*/*"), Some(Mime::ApplicationJson));
+        assert_eq!(
+            negotiate("text/*;q=0.3, text/csv;q=0.9, */*;q=0.1"),
+            Some(Mime::TextCsv)
+        );
+        // 更精确的 q=0 排除了通配符
+        assert_eq!(negotiate("application/json;q=0, */*"), Some(Mime::TextHtml));
+        assert_eq!(negotiate("image/png"), None);
+        assert_eq!(negotiate("text/plain, */*;q=0"), None);
+    }
+
+    #[test]
+    fn test_accept_language() {
+        let available = ["zh-CN", "en-US", "en"];
+        let negotiate = |value: &str| AcceptLanguage::parse(value).negotiate(&available);
+
+        assert_eq!(negotiate("en"), Some("en-US"));
+        assert_eq!(negotiate("en-us, zh;q=0.8"), Some("en-US"));
+        assert_eq!(negotiate("fr, zh;q=0.5"), Some("zh-CN"));
+        assert_eq!(negotiate("en-US;q=0, en"), Some("en"));
+        assert_eq!(negotiate("*;q=0.1, zh;q=0"), Some("en-US"));
+        assert_eq!(negotiate("fr"), None);
+        assert_eq!(negotiate("e"), None);
+    }
+
+    #[test]
+    fn test_accept_charset() {
+        let available = ["utf-8", "iso-8859-1"];
+        let negotiate = |value: &str| AcceptCharset::parse(value).negotiate(&available);
+
+        assert_eq!(negotiate("ISO-8859-1, UTF-8;q=0.5"), Some("iso-8859-1"));
+        assert_eq!(negotiate("*, utf-8;q=0"), Some("iso-8859-1"));
+        assert_eq!(negotiate("gbk"), None);
+    }
+}
@@ -0,0 +1,102 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
+
+/// 实体标签, 用于 `ETag`、`If-Match` 和 `If-None-Match`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityTag {
+    weak: bool,
+    tag: String,
+}
+
+impl EntityTag {
+    /// 强标签, 内容逐字节相同时才相同
+    pub fn strong(tag: impl Into<String>) -> Self {
+        Self {
+            weak: false,
+            tag: tag.into(),
+        }
+    }
+
+    /// 弱标签, 内容语义相同即可
+    pub fn weak(tag: impl Into<String>) -> Self {
+        Self {
+            weak: true,
+            tag: tag.into(),
+        }
+    }
+
+    /// 根据内容计算强标签
+    pub fn from_body(body: &[u8]) -> Self {
+        let hash = Sha256::digest(body);
+        Self::strong(URL_SAFE_NO_PAD.encode(&hash[..16]))
+    }
+
+    /// 解析 `"tag"` 或 `W/"tag"`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (weak, quoted) = match value.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, value),
+        };
+        let tag = quoted.strip_prefix('"')?.strip_suffix('"')?;
+        if tag.contains('"') {
+            return None;
+        }
+        Some(Self {
+            weak,
+            tag: tag.to_string(),
+        })
+    }
+
+    /// 解析逗号分隔的列表, 跳过格式错误的项
+    pub fn parse_list(value: &str) -> Vec<Self> {
+        value.split(',').filter_map(Self::parse).collect()
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// 强比较: 两者都是强标签并且相同
+    pub fn strong_eq(&self, other: &Self) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// 弱比较: 忽略弱标记
+    pub fn weak_eq(&self, other: &Self) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl std::fmt::Display for EntityTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EntityTag;
+
+    #[test]
+    fn test_etag() {
+        let strong = EntityTag::parse("\"xyzzy\"").unwrap();
+        let weak = EntityTag::parse(" W/\"xyzzy\"").unwrap();
+        assert_eq!(strong.to_string(), "\"xyzzy\"");
+        assert_eq!(weak.to_string(), "W/\"xyzzy\"");
+        assert!(strong.strong_eq(&strong));
+        assert!(!strong.strong_eq(&weak));
+        assert!(strong.weak_eq(&weak));
+        assert!(EntityTag::parse("xyzzy").is_none());
+        assert_eq!(EntityTag::parse_list("\"a\", W/\"b\", bad").len(), 2);
+        assert_eq!(EntityTag::from_body(b"a"), EntityTag::from_body(b"a"));
+        assert_ne!(EntityTag::from_body(b"a"), EntityTag::from_body(b"b"));
+    }
+}
@@ -11,6 +11,14 @@ impl HttpHeaders {
     pub fn notify(&mut self, key: &str, value: &str) {
         self.0.insert(key.to_string(), value.to_string());
     }
+
+    /// 获取头部字段, 字段名不区分大小写
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
 }
 
 impl Default for HttpHeaders {
@@ -31,7 +39,7 @@ impl Default for HttpHeaders {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 // 表示该枚举可能会在未来添加新的变体，阻止其他代码直接匹配所有变体
 #[non_exhaustive]
 pub enum Headers {
@@ -40,37 +48,129 @@ pub enum Headers {
     ContentType,
     ContentLength,
     Connection,
+    Cookie,
+    SetCookie,
+    LastModified,
+    Location,
+    Allow,
+    Range,
+    IfRange,
+    AcceptRanges,
+    ContentRange,
+    ETag,
+    IfMatch,
+    IfNoneMatch,
+    IfModifiedSince,
+    IfUnmodifiedSince,
+    AcceptEncoding,
+    ContentEncoding,
+    TransferEncoding,
+    Vary,
+    Accept,
+    AcceptLanguage,
+    AcceptCharset,
+    ContentLanguage,
+    Origin,
+    AccessControlAllowOrigin,
+    AccessControlAllowMethods,
+    AccessControlAllowHeaders,
+    AccessControlAllowCredentials,
+    AccessControlMaxAge,
+    AccessControlExposeHeaders,
+    AccessControlRequestMethod,
+    AccessControlRequestHeaders,
+    CacheControl,
+    LastEventId,
+    Upgrade,
+    SecWebSocketKey,
+    SecWebSocketAccept,
+    SecWebSocketVersion,
+    SecWebSocketProtocol,
     // ....
 }
 
-impl ToString for Headers {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Date => "Date".to_string(),
-            Self::Host => "Host".to_string(),
-            Self::ContentType => "Content-Type".to_string(),
-            Self::ContentLength => "Content-Length".to_string(),
-            Self::Connection => "Connection".to_string(),
-        }
+impl std::fmt::Display for Headers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Date => "Date",
+            Self::Host => "Host",
+            Self::ContentType => "Content-Type",
+            Self::ContentLength => "Content-Length",
+            Self::Connection => "Connection",
+            Self::Cookie => "Cookie",
+            Self::SetCookie => "Set-Cookie",
+            Self::LastModified => "Last-Modified",
+            Self::Location => "Location",
+            Self::Allow => "Allow",
+            Self::Range => "Range",
+            Self::IfRange => "If-Range",
+            Self::AcceptRanges => "Accept-Ranges",
+            Self::ContentRange => "Content-Range",
+            Self::ETag => "ETag",
+            Self::IfMatch => "If-Match",
+            Self::IfNoneMatch => "If-None-Match",
+            Self::IfModifiedSince => "If-Modified-Since",
+            Self::IfUnmodifiedSince => "If-Unmodified-Since",
+            Self::AcceptEncoding => "Accept-Encoding",
+            Self::ContentEncoding => "Content-Encoding",
+            Self::TransferEncoding => "Transfer-Encoding",
+            Self::Vary => "Vary",
+            Self::Accept => "Accept",
+            Self::AcceptLanguage => "Accept-Language",
+            Self::AcceptCharset => "Accept-Charset",
+            Self::ContentLanguage => "Content-Language",
+            Self::Origin => "Origin",
+            Self::AccessControlAllowOrigin => "Access-Control-Allow-Origin",
+            Self::AccessControlAllowMethods => "Access-Control-Allow-Methods",
+            Self::AccessControlAllowHeaders => "Access-Control-Allow-Headers",
+            Self::AccessControlAllowCredentials => "Access-Control-Allow-Credentials",
+            Self::AccessControlMaxAge => "Access-Control-Max-Age",
+            Self::AccessControlExposeHeaders => "Access-Control-Expose-Headers",
+            Self::AccessControlRequestMethod => "Access-Control-Request-Method",
+            Self::AccessControlRequestHeaders => "Access-Control-Request-Headers",
+            Self::CacheControl => "Cache-Control",
+            Self::LastEventId => "Last-Event-ID",
+            Self::Upgrade => "Upgrade",
+            Self::SecWebSocketKey => "Sec-WebSocket-Key",
+            Self::SecWebSocketAccept => "Sec-WebSocket-Accept",
+            Self::SecWebSocketVersion => "Sec-WebSocket-Version",
+            Self::SecWebSocketProtocol => "Sec-WebSocket-Protocol",
+        };
+        f.write_str(s)
     }
 }
 
 #[allow(unused)]
-#[derive(Debug, Hash)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 // 表示该枚举可能会在未来添加新的变体，阻止其他代码直接匹配所有变体
 #[non_exhaustive]
 pub enum Mime {
     TextPlain,
+    TextHtml,
+    TextCsv,
     ApplicationJson,
+    ApplicationWwwFormUrlencoded,
+    MultipartFormData,
+    ApplicationOctetStream,
+    ApplicationXml,
+    TextEventStream,
     // ...
 }
 
-impl ToString for Mime {
-    fn to_string(&self) -> String {
-        match self {
-            Self::TextPlain => "text/plain".to_string(),
-            Self::ApplicationJson => "application/Json".to_string(),
-        }
+impl std::fmt::Display for Mime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::TextPlain => "text/plain",
+            Self::TextHtml => "text/html",
+            Self::TextCsv => "text/csv",
+            Self::ApplicationJson => "application/json",
+            Self::ApplicationWwwFormUrlencoded => "application/x-www-form-urlencoded",
+            Self::MultipartFormData => "multipart/form-data",
+            Self::ApplicationOctetStream => "application/octet-stream",
+            Self::ApplicationXml => "application/xml",
+            Self::TextEventStream => "text/event-stream",
+        };
+        f.write_str(s)
     }
 }
 
@@ -2,28 +2,26 @@ pub trait IntoHttpMethod {
     fn into_http_method(self) -> HttpMethod;
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
 // 表示该枚举可能会在未来添加新的变体，阻止其他代码直接匹配所有变体
 #[non_exhaustive]
 pub enum HttpMethod {
+    #[default]
     GET,
+    HEAD,
     POST,
+    PUT,
+    DELETE,
+    CONNECT,
+    OPTIONS,
+    TRACE,
+    PATCH,
     // ....
 }
 
-impl Default for HttpMethod {
-    fn default() -> Self {
-        Self::GET
-    }
-}
-
 impl IntoHttpMethod for &str {
     fn into_http_method(self) -> HttpMethod {
-        match self {
-            "GET" => HttpMethod::GET,
-            "POST" => HttpMethod::POST,
-            _ => HttpMethod::GET,
-        }
+        self.as_bytes().into()
     }
 }
 
@@ -45,12 +43,9 @@ impl IntoHttpMethod for HttpMethod {
     }
 }
 
-impl Into<Vec<u8>> for HttpMethod {
-    fn into(self) -> Vec<u8> {
-        match self {
-            Self::GET => Vec::from(b"GET"),
-            Self::POST => Vec::from(b"POST"),
-        }
+impl From<HttpMethod> for Vec<u8> {
+    fn from(value: HttpMethod) -> Self {
+        value.to_string().into_bytes()
     }
 }
 
@@ -58,17 +53,32 @@ impl From<&[u8]> for HttpMethod {
     fn from(value: &[u8]) -> Self {
         match value {
             b"GET" => Self::GET,
+            b"HEAD" => Self::HEAD,
             b"POST" => Self::POST,
+            b"PUT" => Self::PUT,
+            b"DELETE" => Self::DELETE,
+            b"CONNECT" => Self::CONNECT,
+            b"OPTIONS" => Self::OPTIONS,
+            b"TRACE" => Self::TRACE,
+            b"PATCH" => Self::PATCH,
             _ => Self::GET,
         }
     }
 }
 
-impl ToString for HttpMethod {
-    fn to_string(&self) -> String {
-        match self {
-            Self::GET => String::from("GET"),
-            Self::POST => String::from("POST"),
-        }
+impl std::fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::GET => "GET",
+            Self::HEAD => "HEAD",
+            Self::POST => "POST",
+            Self::PUT => "PUT",
+            Self::DELETE => "DELETE",
+            Self::CONNECT => "CONNECT",
+            Self::OPTIONS => "OPTIONS",
+            Self::TRACE => "TRACE",
+            Self::PATCH => "PATCH",
+        };
+        f.write_str(s)
     }
 }
@@ -1,6 +1,12 @@
+mod accept;
+pub use accept::{Accept, AcceptCharset, AcceptLanguage, QualityItem, parse_quality_list};
+
+mod etag;
+pub use etag::EntityTag;
+
 mod headers;
 pub(crate) use headers::read_headers;
-pub use headers::{Headers, HttpHeaders};
+pub use headers::{Headers, HttpHeaders, Mime};
 
 mod method;
 pub use method::{HttpMethod, IntoHttpMethod};
@@ -8,6 +14,5 @@ pub use method::{HttpMethod, IntoHttpMethod};
 mod status_code;
 pub use status_code::{IntoStatusCode, StatusCode};
 
-///
 mod version;
 pub use version::{HttpVersion, IntoHttpVersion};
@@ -2,12 +2,28 @@ pub trait IntoStatusCode {
     fn into_status_code(self) -> StatusCode;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 // 表示该枚举可能会在未来添加新的变体，阻止其他代码直接匹配所有变体
 #[non_exhaustive]
 pub enum StatusCode {
+    SwitchingProtocols,
+    #[default]
     OK,
+    NoContent,
+    PartialContent,
+    MovedPermanently,
+    NotModified,
+    BadRequest,
     NotFound,
+    MethodNotAllowed,
+    NotAcceptable,
+    PreconditionFailed,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    RangeNotSatisfiable,
+    UnprocessableEntity,
+    UpgradeRequired,
+    InternalServerError,
     // ....
 }
 
@@ -20,8 +36,23 @@ impl IntoStatusCode for StatusCode {
 impl IntoStatusCode for &str {
     fn into_status_code(self) -> StatusCode {
         match self {
+            "SwitchingProtocols" => StatusCode::SwitchingProtocols,
             "OK" | "Ok" | "ok" | "oK" => StatusCode::OK,
+            "NoContent" => StatusCode::NoContent,
+            "PartialContent" => StatusCode::PartialContent,
+            "MovedPermanently" => StatusCode::MovedPermanently,
+            "NotModified" => StatusCode::NotModified,
+            "BadRequest" => StatusCode::BadRequest,
             "NotFound" => StatusCode::NotFound,
+            "MethodNotAllowed" => StatusCode::MethodNotAllowed,
+            "NotAcceptable" => StatusCode::NotAcceptable,
+            "PreconditionFailed" => StatusCode::PreconditionFailed,
+            "PayloadTooLarge" => StatusCode::PayloadTooLarge,
+            "UnsupportedMediaType" => StatusCode::UnsupportedMediaType,
+            "RangeNotSatisfiable" => StatusCode::RangeNotSatisfiable,
+            "UnprocessableEntity" => StatusCode::UnprocessableEntity,
+            "UpgradeRequired" => StatusCode::UpgradeRequired,
+            "InternalServerError" => StatusCode::InternalServerError,
             _ => StatusCode::NotFound,
         }
     }
@@ -42,8 +73,23 @@ impl IntoStatusCode for &String {
 impl IntoStatusCode for u128 {
     fn into_status_code(self) -> StatusCode {
         match self {
+            101 => StatusCode::SwitchingProtocols,
             200 => StatusCode::OK,
+            204 => StatusCode::NoContent,
+            206 => StatusCode::PartialContent,
+            301 => StatusCode::MovedPermanently,
+            304 => StatusCode::NotModified,
+            400 => StatusCode::BadRequest,
             404 => StatusCode::NotFound,
+            405 => StatusCode::MethodNotAllowed,
+            406 => StatusCode::NotAcceptable,
+            412 => StatusCode::PreconditionFailed,
+            413 => StatusCode::PayloadTooLarge,
+            415 => StatusCode::UnsupportedMediaType,
+            416 => StatusCode::RangeNotSatisfiable,
+            422 => StatusCode::UnprocessableEntity,
+            426 => StatusCode::UpgradeRequired,
+            500 => StatusCode::InternalServerError,
             _ => StatusCode::NotFound,
         }
     }
@@ -67,17 +113,26 @@ impl IntoStatusCode for u16 {
     }
 }
 
-impl Into<Vec<u8>> for StatusCode {
-    fn into(self) -> Vec<u8> {
-        match self {
-            Self::OK => Vec::from(b"200 OK"),
-            Self::NotFound => Vec::from(b"404 Not Found"),
+impl From<StatusCode> for Vec<u8> {
+    fn from(value: StatusCode) -> Self {
+        match value {
+            StatusCode::SwitchingProtocols => Vec::from(b"101 Switching Protocols"),
+            StatusCode::OK => Vec::from(b"200 OK"),
+            StatusCode::NoContent => Vec::from(b"204 No Content"),
+            StatusCode::PartialContent => Vec::from(b"206 Partial Content"),
+            StatusCode::MovedPermanently => Vec::from(b"301 Moved Permanently"),
+            StatusCode::NotModified => Vec::from(b"304 Not Modified"),
+            StatusCode::BadRequest => Vec::from(b"400 Bad Request"),
+            StatusCode::NotFound => Vec::from(b"404 Not Found"),
+            StatusCode::MethodNotAllowed => Vec::from(b"405 Method Not Allowed"),
+            StatusCode::NotAcceptable => Vec::from(b"406 Not Acceptable"),
+            StatusCode::PreconditionFailed => Vec::from(b"412 Precondition Failed"),
+            StatusCode::PayloadTooLarge => Vec::from(b"413 Payload Too Large"),
+            StatusCode::UnsupportedMediaType => Vec::from(b"415 Unsupported Media Type"),
+            StatusCode::RangeNotSatisfiable => Vec::from(b"416 Range Not Satisfiable"),
+            StatusCode::UnprocessableEntity => Vec::from(b"422 Unprocessable Entity"),
+            StatusCode::UpgradeRequired => Vec::from(b"426 Upgrade Required"),
+            StatusCode::InternalServerError => Vec::from(b"500 Internal Server Error"),
         }
     }
 }
-
-impl Default for StatusCode {
-    fn default() -> Self {
-        Self::OK
-    }
-}
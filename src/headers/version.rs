@@ -2,22 +2,17 @@ pub trait IntoHttpVersion {
     fn into_http_version(self) -> HttpVersion;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 // 表示该枚举可能会在未来添加新的变体，阻止其他代码直接匹配所有变体
 #[non_exhaustive]
 pub enum HttpVersion {
     V1_0,
+    #[default]
     V1_1,
     V2,
     // ....
 }
 
-impl Default for HttpVersion {
-    fn default() -> Self {
-        Self::V1_1
-    }
-}
-
 impl IntoHttpVersion for &str {
     fn into_http_version(self) -> HttpVersion {
         match self {
@@ -46,22 +41,23 @@ impl From<&[u8]> for HttpVersion {
     }
 }
 
-impl Into<Vec<u8>> for HttpVersion {
-    fn into(self) -> Vec<u8> {
-        match self {
-            Self::V1_0 => Vec::from(b"HTTP/1.0"),
-            Self::V1_1 => Vec::from(b"HTTP/1.1"),
-            Self::V2 => Vec::from(b"HTTP/2"),
+impl From<HttpVersion> for Vec<u8> {
+    fn from(value: HttpVersion) -> Self {
+        match value {
+            HttpVersion::V1_0 => Vec::from(b"HTTP/1.0"),
+            HttpVersion::V1_1 => Vec::from(b"HTTP/1.1"),
+            HttpVersion::V2 => Vec::from(b"HTTP/2"),
         }
     }
 }
 
-impl ToString for HttpVersion {
-    fn to_string(&self) -> String {
-        match self {
-            Self::V1_0 => String::from("HTTP/1.0"),
-            Self::V1_1 => String::from("HTTP/1.1"),
-            Self::V2 => String::from("HTTP/2"),
-        }
+impl std::fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::V1_0 => "HTTP/1.0",
+            Self::V1_1 => "HTTP/1.1",
+            Self::V2 => "HTTP/2",
+        };
+        f.write_str(s)
     }
 }
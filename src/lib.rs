@@ -1,12 +1,14 @@
+#![allow(clippy::module_inception)]
+
 // 所有的错误类型
-mod error;
+pub mod error;
 
 // 工具类
 mod utils;
 
 // 请求类
 mod request;
-pub use request::{Request, handle_request};
+pub use request::{BodyReader, Extensions, QueryMap, Request, handle_request, target};
 
 // 响应类
 pub mod response;
@@ -19,11 +21,25 @@ pub mod headers;
 
 // 路由类
 mod router;
-pub use router::Router;
+pub use router::{Route, Router};
+
+// 中间件
+pub mod middleware;
+
+pub mod cookie;
+pub mod session;
+
+pub mod services;
 
 // 服务启动类
 mod server;
-pub use server::serve;
+pub use server::{IncomingStream, Server, Service, serve};
 
 // 处理类
 mod handle;
+pub use handle::{Handler, HandlerService, IntoServiceMarker};
+
+// 提取器
+pub mod extract;
+
+pub mod ws;
@@ -17,7 +17,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/", HttpMethod::GET, "Hello, World!".to_string())
         .route("/hello", "get", "Hello, This is a test".to_string())
         .route("/post", "POST", hello)
-        .route("/", "POST", |_| "Hello, Rust");
+        .route("/", "POST", |_: Request| "Hello, Rust");
 
     serve(listener, app);
 
@@ -0,0 +1,296 @@
+//! 响应压缩中间件
+
+use std::io::Read;
+
+use crate::{
+    error::ResponseError,
+    headers::{Headers, HttpMethod, StatusCode},
+    request::Request,
+    response::Response,
+    server::Service,
+};
+
+use super::{
+    Layer,
+    encoding::{self, Encoding},
+};
+
+/// 按 `Accept-Encoding` 压缩响应体
+///
+/// 支持 `br`、`gzip` 和 `deflate`, 设置 `Content-Encoding` 并在 `Vary` 中添加 `Accept-Encoding`。
+/// 图片、音视频、压缩包等已经压缩过的类型, 以及小于 [`min_size`](Self::min_size) 的响应体不压缩。
+/// 流式响应体边读取边压缩。压缩后强 `ETag` 会改为弱 `ETag`
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, middleware::CompressionLayer};
+///
+/// let app = Router::new()
+///     .route("/", "GET", "hello")
+///     .layer(CompressionLayer::new().min_size(256));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompressionLayer {
+    enabled: Vec<Encoding>,
+    min_size: usize,
+}
+
+impl Default for CompressionLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompressionLayer {
+    /// 默认启用所有编码, 小于 1 KB 的响应体不压缩
+    pub fn new() -> Self {
+        Self {
+            enabled: Encoding::ALL.to_vec(),
+            min_size: 1024,
+        }
+    }
+
+    pub fn br(self, enable: bool) -> Self {
+        self.toggle(Encoding::Brotli, enable)
+    }
+
+    pub fn gzip(self, enable: bool) -> Self {
+        self.toggle(Encoding::Gzip, enable)
+    }
+
+    pub fn deflate(self, enable: bool) -> Self {
+        self.toggle(Encoding::Deflate, enable)
+    }
+
+    /// 小于这个长度的响应体不压缩, 流式响应体总是压缩
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    fn toggle(mut self, encoding: Encoding, enable: bool) -> Self {
+        self.enabled.retain(|e| *e != encoding);
+        if enable {
+            self.enabled.push(encoding);
+        }
+        self
+    }
+}
+
+impl<S> Layer<S> for CompressionLayer {
+    type Service = CompressionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CompressionService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// [`CompressionLayer`] 包装后的服务
+#[derive(Debug, Clone)]
+pub struct CompressionService<S> {
+    inner: S,
+    layer: CompressionLayer,
+}
+
+impl<S> Service<Request> for CompressionService<S>
+where
+    S: Service<Request, Response = Response, Error = ResponseError>,
+{
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        let head = *req.method_ref() == HttpMethod::HEAD;
+        let accept = req
+            .headers
+            .get(&Headers::AcceptEncoding.to_string())
+            .map(str::to_string);
+        let resp = self.inner.call(req)?;
+        if head || !compressible(&resp) {
+            return Ok(resp);
+        }
+
+        let resp = resp.vary(Headers::AcceptEncoding);
+        if !resp.is_stream() && resp.body_ref().len() < self.layer.min_size {
+            return Ok(resp);
+        }
+        let Some(encoding) = accept.and_then(|a| encoding::negotiate(&a, &self.layer.enabled))
+        else {
+            return Ok(resp);
+        };
+        Ok(compress(resp, encoding))
+    }
+}
+
+/// 是否需要根据 `Accept-Encoding` 压缩
+fn compressible(resp: &Response) -> bool {
+    let status = resp.status_ref();
+    if *status == StatusCode::PartialContent || *status == StatusCode::NotModified {
+        return false;
+    }
+    let headers = resp.headers_ref();
+    if headers.get(&Headers::ContentEncoding.to_string()).is_some() {
+        return false;
+    }
+    let content_type = headers
+        .get(&Headers::ContentType.to_string())
+        .unwrap_or_default();
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if let Some(subtype) = mime.strip_prefix("image/") {
+        return subtype == "svg+xml";
+    }
+    !(mime.starts_with("audio/")
+        || mime.starts_with("video/")
+        || matches!(
+            mime.as_str(),
+            "application/zip"
+                | "application/gzip"
+                | "application/x-gzip"
+                | "application/x-bzip2"
+                | "application/x-7z-compressed"
+                | "application/x-rar-compressed"
+                | "application/zstd"
+                | "font/woff"
+                | "font/woff2"
+                // 压缩器会缓冲数据, 事件不能及时送达
+                | "text/event-stream"
+        ))
+}
+
+fn compress(resp: Response, encoding: Encoding) -> Response {
+    // 压缩后的内容和原来不再逐字节相同
+    let etag = resp
+        .headers_ref()
+        .get(&Headers::ETag.to_string())
+        .filter(|etag| !etag.starts_with("W/"))
+        .map(|etag| format!("W/{etag}"));
+    let mut resp = resp.header(Headers::ContentEncoding, encoding.as_str());
+    if let Some(etag) = etag {
+        resp = resp.header(Headers::ETag, &etag);
+    }
+
+    if resp.is_stream() {
+        return resp.map_stream(|reader| encoding.encoder(reader));
+    }
+    let mut body = Vec::new();
+    encoding
+        .encoder(resp.body_ref())
+        .read_to_end(&mut body)
+        .expect("压缩内存中的响应体不会失败");
+    resp.raw_body(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use flate2::read::{GzDecoder, ZlibDecoder};
+
+    use super::CompressionLayer;
+    use crate::{Request, Router, headers::Headers, response::Response};
+
+    /// 拆分响应头和响应体
+    fn send(router: &mut Router, req: Request) -> (String, Vec<u8>) {
+        let resp: Vec<u8> = router.handle(req).into();
+        let split = resp.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 2;
+        let head = String::from_utf8(resp[..split].to_vec()).unwrap();
+        (head, resp[split + 2..].to_vec())
+    }
+
+    fn get(path: &str, accept: &str) -> Request {
+        Request::new()
+            .path(path)
+            .unwrap()
+            .headers(Headers::AcceptEncoding, accept)
+    }
+
+    /// 解析 chunked 编码
+    fn dechunk(mut body: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        loop {
+            let line = body.windows(2).position(|w| w == b"\r\n").unwrap();
+            let len = usize::from_str_radix(std::str::from_utf8(&body[..line]).unwrap(), 16);
+            let len = len.unwrap();
+            if len == 0 {
+                return data;
+            }
+            data.extend_from_slice(&body[line + 2..line + 2 + len]);
+            body = &body[line + 4 + len..];
+        }
+    }
+
+    #[test]
+    fn test_compression() {
+        let text = "hello world ".repeat(200);
+        let body = text.clone();
+        let stream = text.clone();
+        let mut router = Router::new()
+            .route("/", "GET", move || body.clone())
+            .route("/small", "GET", "small")
+            .route("/png", "GET", || {
+                Response::new()
+                    .header(Headers::ContentType, "image/png")
+                    .raw_body(vec![0; 4096])
+            })
+            .route("/stream", "GET", move || {
+                Response::new().stream(Cursor::new(stream.clone().into_bytes()))
+            })
+            .layer(CompressionLayer::new());
+        let expected = text.clone().into_bytes();
+
+        let (head, body) = send(&mut router, get("/", "gzip;q=0.9, deflate;q=0.5"));
+        assert!(head.contains("Content-Encoding: gzip\r\n"));
+        assert!(head.contains("Vary: Accept-Encoding\r\n"));
+        assert!(head.contains(&format!("Content-Length: {}\r\n", body.len())));
+        let mut data = Vec::new();
+        GzDecoder::new(body.as_slice())
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, expected);
+
+        let (head, body) = send(&mut router, get("/", "deflate"));
+        assert!(head.contains("Content-Encoding: deflate\r\n"));
+        let mut data = Vec::new();
+        ZlibDecoder::new(body.as_slice())
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, expected);
+
+        let (head, body) = send(&mut router, get("/", "gzip, br"));
+        assert!(head.contains("Content-Encoding: br\r\n"));
+        let mut data = Vec::new();
+        brotli::Decompressor::new(body.as_slice(), 4096)
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, expected);
+
+        let (head, body) = send(&mut router, get("/", "identity"));
+        assert!(!head.contains("Content-Encoding"));
+        assert!(head.contains("Vary: Accept-Encoding\r\n"));
+        assert_eq!(body, expected);
+
+        let (head, _) = send(&mut router, get("/small", "gzip"));
+        assert!(!head.contains("Content-Encoding"));
+        let (head, _) = send(&mut router, get("/png", "gzip"));
+        assert!(!head.contains("Content-Encoding"));
+        assert!(!head.contains("Vary"));
+
+        let (head, body) = send(&mut router, get("/stream", "gzip"));
+        assert!(head.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(head.contains("Content-Encoding: gzip\r\n"));
+        assert!(!head.contains("Content-Length"));
+        let mut data = Vec::new();
+        GzDecoder::new(dechunk(&body).as_slice())
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, text.into_bytes());
+    }
+}
@@ -0,0 +1,165 @@
+//! 条件请求中间件
+
+use crate::{
+    error::ResponseError,
+    extract::Preconditions,
+    headers::{EntityTag, Headers, HttpMethod, StatusCode},
+    request::Request,
+    response::Response,
+    server::Service,
+};
+
+use super::Layer;
+
+/// 为 `GET` 和 `HEAD` 请求处理条件头部
+///
+/// 只处理 200 响应。响应没有 `ETag` 时根据响应体计算强 `ETag`,
+/// 然后按 `If-None-Match`、`If-Modified-Since` 等返回 304 或 412。
+/// 和 [`RangeLayer`](super::RangeLayer) 一起使用时放在内层, 让 `If-Range` 可以使用计算出的 `ETag`
+///
+/// # Example
+/// ```rust
+/// use http_sv::{
+///     Router,
+///     middleware::{ConditionalLayer, RangeLayer},
+/// };
+///
+/// let app = Router::new()
+///     .route("/report.csv", "GET", "id,name\n1,bing\n")
+///     .layer(ConditionalLayer::new())
+///     .layer(RangeLayer::new());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ConditionalLayer {
+    compute_etag: bool,
+}
+
+impl Default for ConditionalLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConditionalLayer {
+    pub fn new() -> Self {
+        Self { compute_etag: true }
+    }
+
+    /// 响应没有 `ETag` 时是否根据响应体计算, 默认为 `true`
+    pub fn compute_etag(mut self, compute_etag: bool) -> Self {
+        self.compute_etag = compute_etag;
+        self
+    }
+}
+
+impl<S> Layer<S> for ConditionalLayer {
+    type Service = ConditionalService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConditionalService {
+            inner,
+            compute_etag: self.compute_etag,
+        }
+    }
+}
+
+/// [`ConditionalLayer`] 包装后的服务
+#[derive(Debug, Clone)]
+pub struct ConditionalService<S> {
+    inner: S,
+    compute_etag: bool,
+}
+
+impl<S> Service<Request> for ConditionalService<S>
+where
+    S: Service<Request, Response = Response, Error = ResponseError>,
+{
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        let method = *req.method_ref();
+        if !matches!(method, HttpMethod::GET | HttpMethod::HEAD) {
+            return self.inner.call(req);
+        }
+        let preconditions = Preconditions::from_request(&req);
+        let mut resp = self.inner.call(req)?;
+        if *resp.status_ref() != StatusCode::OK {
+            return Ok(resp);
+        }
+        // HEAD 的响应体为空, 计算出的 ETag 和 GET 不一致, 流式响应体还没有读取
+        let has_etag = resp.headers_ref().get(&Headers::ETag.to_string()).is_some();
+        if self.compute_etag && !has_etag && method == HttpMethod::GET && !resp.is_stream() {
+            let etag = EntityTag::from_body(resp.body_ref());
+            resp = resp.etag(&etag);
+        }
+        Ok(preconditions.apply(&method, resp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConditionalLayer;
+    use crate::{Request, Router, headers::Headers, response::Response};
+
+    fn send(router: &mut Router, req: Request) -> String {
+        let resp: Vec<u8> = router.handle(req).into();
+        String::from_utf8(resp).unwrap()
+    }
+
+    #[test]
+    fn test_conditional() {
+        let mut router = Router::new()
+            .route("/", "GET", "hello")
+            .route("/fixed", "GET", || {
+                Response::new()
+                    .header(Headers::ETag, "W/\"v1\"")
+                    .header(Headers::LastModified, "Wed, 21 Oct 2015 07:28:00 GMT")
+                    .body("fixed".into())
+            })
+            .layer(ConditionalLayer::new());
+
+        let resp = send(&mut router, Request::new().path("/").unwrap());
+        let etag = resp
+            .split("ETag: ")
+            .nth(1)
+            .unwrap()
+            .split("\r\n")
+            .next()
+            .unwrap()
+            .to_string();
+        assert!(resp.starts_with("HTTP/1.1 200"));
+
+        let req = Request::new()
+            .path("/")
+            .unwrap()
+            .headers(Headers::IfNoneMatch, etag.as_str());
+        let resp = send(&mut router, req);
+        assert!(resp.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+        assert!(resp.contains(&format!("ETag: {etag}\r\n")));
+        assert!(!resp.contains("Content-Length"));
+        assert!(resp.ends_with("\r\n\r\n"));
+
+        let req = Request::new()
+            .path("/")
+            .unwrap()
+            .headers(Headers::IfNoneMatch, "\"other\"");
+        assert!(send(&mut router, req).starts_with("HTTP/1.1 200"));
+
+        let req = Request::new()
+            .path("/fixed")
+            .unwrap()
+            .headers(Headers::IfNoneMatch, "\"v1\"");
+        assert!(send(&mut router, req).starts_with("HTTP/1.1 304"));
+        let req = Request::new()
+            .path("/fixed")
+            .unwrap()
+            .headers(Headers::IfModifiedSince, "Wed, 21 Oct 2015 07:28:00 GMT");
+        assert!(send(&mut router, req).starts_with("HTTP/1.1 304"));
+        let req = Request::new()
+            .path("/fixed")
+            .unwrap()
+            .headers(Headers::IfMatch, "\"v1\"");
+        assert!(send(&mut router, req).starts_with("HTTP/1.1 412 Precondition Failed"));
+    }
+}
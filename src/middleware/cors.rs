@@ -0,0 +1,441 @@
+//! 跨域资源共享中间件
+
+use std::{fmt, sync::Arc};
+
+use chrono::Duration;
+
+use crate::{
+    error::ResponseError,
+    headers::{Headers, HttpMethod, IntoHttpMethod, StatusCode},
+    request::Request,
+    response::Response,
+    server::Service,
+};
+
+use super::Layer;
+
+/// 允许的来源
+#[derive(Clone)]
+pub enum AllowOrigin {
+    /// 任何来源
+    Any,
+    /// 列表中的来源, 比较时不区分大小写
+    List(Vec<String>),
+    /// 由函数判断
+    Predicate(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+impl AllowOrigin {
+    pub fn any() -> Self {
+        Self::Any
+    }
+
+    pub fn exact(origin: impl Into<String>) -> Self {
+        Self::List(vec![origin.into()])
+    }
+
+    pub fn list<I, T>(origins: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self::List(origins.into_iter().map(Into::into).collect())
+    }
+
+    pub fn predicate(f: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self::Predicate(Arc::new(f))
+    }
+
+    fn allows(&self, origin: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::List(origins) => origins.iter().any(|o| o.eq_ignore_ascii_case(origin)),
+            Self::Predicate(f) => f(origin),
+        }
+    }
+}
+
+impl fmt::Debug for AllowOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("Any"),
+            Self::List(origins) => f.debug_tuple("List").field(origins).finish(),
+            Self::Predicate(_) => f.write_str("Predicate(..)"),
+        }
+    }
+}
+
+/// 处理跨域请求
+///
+/// 带 `Origin` 和 `Access-Control-Request-Method` 的 `OPTIONS` 请求作为预检请求直接响应 204,
+/// 其他请求交给内层服务后添加 `Access-Control-Allow-Origin` 等头部。
+/// 来源不被允许时不添加任何 CORS 头部, 由浏览器拒绝。
+/// 响应随 `Origin` 变化时在 `Vary` 中添加 `Origin`。
+///
+/// 需要通过 [`Router::layer`](crate::Router::layer) 添加, 路径上没有 `OPTIONS` 路由时
+/// 预检请求才会经过这个中间件
+///
+/// # Example
+/// ```rust
+/// use chrono::Duration;
+/// use http_sv::{
+///     Router,
+///     middleware::{AllowOrigin, CorsLayer},
+/// };
+///
+/// let cors = CorsLayer::new()
+///     .allow_origin(AllowOrigin::list(["https://app.example.com"]))
+///     .allow_methods(["GET", "POST", "DELETE"])
+///     .allow_headers(["Content-Type", "Authorization"])
+///     .allow_credentials(true)
+///     .max_age(Duration::hours(1));
+///
+/// let app = Router::new()
+///     .route("/api/users", "GET", "[]")
+///     .route("/api/users", "POST", "created")
+///     .layer(cors);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CorsLayer {
+    origin: AllowOrigin,
+    /// `None` 时允许预检请求中的任何方法
+    methods: Option<Vec<HttpMethod>>,
+    /// `None` 时允许预检请求中的任何头部
+    headers: Option<Vec<String>>,
+    credentials: bool,
+    max_age: Option<Duration>,
+    expose_headers: Vec<String>,
+}
+
+impl Default for CorsLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CorsLayer {
+    /// 不允许任何来源, 允许 `GET`、`HEAD` 和 `POST`, 不允许额外的请求头
+    pub fn new() -> Self {
+        Self {
+            origin: AllowOrigin::List(Vec::new()),
+            methods: Some(vec![HttpMethod::GET, HttpMethod::HEAD, HttpMethod::POST]),
+            headers: Some(Vec::new()),
+            credentials: false,
+            max_age: None,
+            expose_headers: Vec::new(),
+        }
+    }
+
+    /// 允许任何来源、方法和请求头, 不允许携带凭据, 适合开发环境
+    pub fn permissive() -> Self {
+        Self::new()
+            .allow_origin(AllowOrigin::Any)
+            .allow_any_method()
+            .allow_any_header()
+    }
+
+    pub fn allow_origin(mut self, origin: AllowOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn allow_methods<I, M>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = M>,
+        M: IntoHttpMethod,
+    {
+        self.methods = Some(
+            methods
+                .into_iter()
+                .map(IntoHttpMethod::into_http_method)
+                .collect(),
+        );
+        self
+    }
+
+    /// 预检请求的 `Access-Control-Allow-Methods` 使用请求的方法
+    pub fn allow_any_method(mut self) -> Self {
+        self.methods = None;
+        self
+    }
+
+    pub fn allow_headers<I, T>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.headers = Some(headers.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// 预检请求的 `Access-Control-Allow-Headers` 使用请求的头部
+    pub fn allow_any_header(mut self) -> Self {
+        self.headers = None;
+        self
+    }
+
+    /// 是否允许携带 cookie 等凭据
+    ///
+    /// 允许凭据时必须用列表或函数限定来源, 和 [`AllowOrigin::Any`] 一起使用时
+    /// [`layer`](Layer::layer) 会 panic
+    pub fn allow_credentials(mut self, credentials: bool) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// 浏览器缓存预检结果的时间
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// 允许浏览器脚本读取的响应头
+    pub fn expose_headers<I, T>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.expose_headers = headers.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 响应是否随 `Origin` 变化
+    fn varies(&self) -> bool {
+        !matches!(self.origin, AllowOrigin::Any)
+    }
+
+    /// `Access-Control-Allow-Origin` 的值, 来源不被允许时返回 `None`
+    fn allow_origin_value(&self, origin: &str) -> Option<String> {
+        if !self.origin.allows(origin) {
+            return None;
+        }
+        match self.varies() {
+            true => Some(origin.to_string()),
+            false => Some("*".to_string()),
+        }
+    }
+
+    fn preflight(&self, req: &Request, origin: &str) -> Response {
+        let mut resp = Response::new()
+            .status(StatusCode::NoContent)
+            .remove_header(Headers::ContentType);
+        if self.varies() {
+            resp = resp.vary(Headers::Origin);
+        }
+        resp = resp
+            .vary(Headers::AccessControlRequestMethod)
+            .vary(Headers::AccessControlRequestHeaders);
+        let Some(allow_origin) = self.allow_origin_value(origin) else {
+            return resp;
+        };
+
+        let request_header = |key: Headers| req.headers.get(&key.to_string()).unwrap_or_default();
+        let methods = match &self.methods {
+            Some(methods) => join(methods),
+            None => request_header(Headers::AccessControlRequestMethod).to_string(),
+        };
+        let headers = match &self.headers {
+            Some(headers) => headers.join(", "),
+            None => request_header(Headers::AccessControlRequestHeaders).to_string(),
+        };
+        resp = resp
+            .header(Headers::AccessControlAllowOrigin, &allow_origin)
+            .header(Headers::AccessControlAllowMethods, &methods);
+        if !headers.is_empty() {
+            resp = resp.header(Headers::AccessControlAllowHeaders, &headers);
+        }
+        if self.credentials {
+            resp = resp.header(Headers::AccessControlAllowCredentials, "true");
+        }
+        if let Some(max_age) = self.max_age {
+            resp = resp.header(
+                Headers::AccessControlMaxAge,
+                &max_age.num_seconds().max(0).to_string(),
+            );
+        }
+        resp
+    }
+
+    fn apply(&self, mut resp: Response, origin: Option<&str>) -> Response {
+        if self.varies() {
+            resp = resp.vary(Headers::Origin);
+        }
+        let Some(allow_origin) = origin.and_then(|origin| self.allow_origin_value(origin)) else {
+            return resp;
+        };
+        resp = resp.header(Headers::AccessControlAllowOrigin, &allow_origin);
+        if self.credentials {
+            resp = resp.header(Headers::AccessControlAllowCredentials, "true");
+        }
+        if !self.expose_headers.is_empty() {
+            resp = resp.header(
+                Headers::AccessControlExposeHeaders,
+                &self.expose_headers.join(", "),
+            );
+        }
+        resp
+    }
+}
+
+fn join(methods: &[HttpMethod]) -> String {
+    let methods: Vec<String> = methods.iter().map(ToString::to_string).collect();
+    methods.join(", ")
+}
+
+impl<S> Layer<S> for CorsLayer {
+    type Service = CorsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        // 回显任意来源并允许凭据会让任何网站都能读取带凭据的响应
+        assert!(
+            !(self.credentials && matches!(self.origin, AllowOrigin::Any)),
+            "CorsLayer: allow_credentials(true) 不能和 AllowOrigin::Any 一起使用"
+        );
+        CorsService {
+            inner,
+            layer: Arc::new(self.clone()),
+        }
+    }
+}
+
+/// [`CorsLayer`] 包装后的服务
+#[derive(Debug, Clone)]
+pub struct CorsService<S> {
+    inner: S,
+    layer: Arc<CorsLayer>,
+}
+
+impl<S> Service<Request> for CorsService<S>
+where
+    S: Service<Request, Response = Response, Error = ResponseError>,
+{
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        let origin = req
+            .headers
+            .get(&Headers::Origin.to_string())
+            .map(str::to_string);
+        let preflight = *req.method_ref() == HttpMethod::OPTIONS
+            && req
+                .headers
+                .get(&Headers::AccessControlRequestMethod.to_string())
+                .is_some();
+        if let Some(origin) = &origin
+            && preflight
+        {
+            return Ok(self.layer.preflight(&req, origin));
+        }
+        let resp = self.inner.call(req)?;
+        Ok(self.layer.apply(resp, origin.as_deref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::{AllowOrigin, CorsLayer};
+    use crate::{Request, Router, headers::Headers};
+
+    fn send(router: &mut Router, req: Request) -> String {
+        let resp: Vec<u8> = router.handle(req).into();
+        String::from_utf8(resp).unwrap()
+    }
+
+    fn preflight(origin: &str) -> Request {
+        Request::new()
+            .method("OPTIONS")
+            .path("/api")
+            .unwrap()
+            .headers(Headers::Origin, origin)
+            .headers(Headers::AccessControlRequestMethod, "POST")
+            .headers(Headers::AccessControlRequestHeaders, "content-type")
+    }
+
+    #[test]
+    fn test_preflight() {
+        let cors = CorsLayer::new()
+            .allow_origin(AllowOrigin::exact("https://app.example.com"))
+            .allow_methods(["GET", "POST"])
+            .allow_headers(["Content-Type"])
+            .allow_credentials(true)
+            .max_age(Duration::minutes(10));
+        let mut router = Router::new()
+            .route("/api", "GET", "get")
+            .route("/api", "POST", "post")
+            .layer(cors);
+
+        let resp = send(&mut router, preflight("https://app.example.com"));
+        assert!(resp.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(resp.contains("Access-Control-Allow-Origin: https://app.example.com\r\n"));
+        assert!(resp.contains("Access-Control-Allow-Methods: GET, POST\r\n"));
+        assert!(resp.contains("Access-Control-Allow-Headers: Content-Type\r\n"));
+        assert!(resp.contains("Access-Control-Allow-Credentials: true\r\n"));
+        assert!(resp.contains("Access-Control-Max-Age: 600\r\n"));
+        assert!(resp.contains(
+            "Vary: Origin, Access-Control-Request-Method, Access-Control-Request-Headers\r\n"
+        ));
+        assert!(!resp.ends_with("post\r\n"));
+
+        let resp = send(&mut router, preflight("https://evil.example.com"));
+        assert!(resp.starts_with("HTTP/1.1 204"));
+        assert!(!resp.contains("Access-Control-Allow-Origin"));
+
+        // 没有 Access-Control-Request-Method 的 OPTIONS 不是预检请求
+        let req = Request::new()
+            .method("OPTIONS")
+            .path("/api")
+            .unwrap()
+            .headers(Headers::Origin, "https://app.example.com");
+        let resp = send(&mut router, req);
+        assert!(resp.contains("Allow: GET, POST, OPTIONS\r\n"));
+    }
+
+    #[test]
+    fn test_simple_request() {
+        let cors = CorsLayer::new()
+            .allow_origin(AllowOrigin::predicate(|origin| {
+                origin.ends_with(".example.com")
+            }))
+            .expose_headers(["X-Total-Count"]);
+        let mut router = Router::new().route("/api", "GET", "get").layer(cors);
+
+        let req = Request::new()
+            .path("/api")
+            .unwrap()
+            .headers(Headers::Origin, "https://a.example.com");
+        let resp = send(&mut router, req);
+        assert!(resp.contains("Access-Control-Allow-Origin: https://a.example.com\r\n"));
+        assert!(resp.contains("Access-Control-Expose-Headers: X-Total-Count\r\n"));
+        assert!(resp.contains("Vary: Origin\r\n"));
+        assert!(resp.ends_with("\r\n\r\nget"));
+
+        let resp = send(&mut router, Request::new().path("/api").unwrap());
+        assert!(!resp.contains("Access-Control-Allow-Origin"));
+        assert!(resp.contains("Vary: Origin\r\n"));
+
+        let mut router = Router::new()
+            .route("/api", "GET", "get")
+            .layer(CorsLayer::permissive());
+        let req = Request::new()
+            .path("/api")
+            .unwrap()
+            .headers(Headers::Origin, "https://other.org");
+        let resp = send(&mut router, req);
+        assert!(resp.contains("Access-Control-Allow-Origin: *\r\n"));
+        assert!(!resp.contains("Vary"));
+        let resp = send(&mut router, preflight("https://other.org"));
+        assert!(resp.contains("Access-Control-Allow-Methods: POST\r\n"));
+        assert!(resp.contains("Access-Control-Allow-Headers: content-type\r\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "allow_credentials")]
+    fn test_any_origin_with_credentials() {
+        let _ = Router::new()
+            .route("/api", "GET", "get")
+            .layer(CorsLayer::permissive().allow_credentials(true));
+    }
+}
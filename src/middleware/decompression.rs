@@ -0,0 +1,286 @@
+//! 请求体解压中间件
+
+use std::io::{self, Read};
+
+use crate::{
+    error::ResponseError,
+    headers::{Headers, StatusCode},
+    request::{BodyReader, Request},
+    response::Response,
+    server::Service,
+};
+
+use super::{Layer, encoding::Encoding};
+
+/// 解压带 `Content-Encoding` 的请求体, 提取器看到的是解压后的内容
+///
+/// 支持 `br`、`gzip` 和 `deflate`, 其他编码返回 415 并在 `Accept-Encoding` 中列出支持的编码。
+/// 解压后超过 [`limit`](Self::limit) 时返回 413, 内容损坏时返回 400。
+/// 压缩的请求体本身的长度由 [`Router::max_body_size`](crate::Router::max_body_size) 限制,
+/// `multipart/form-data` 等流式读取的请求体边读取边解压, 超过限制时读取失败
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, middleware::DecompressionLayer};
+///
+/// fn upload(body: String) -> String {
+///     format!("{} bytes", body.len())
+/// }
+///
+/// let app = Router::new()
+///     .route("/upload", "POST", upload)
+///     .layer(DecompressionLayer::new().limit(1024 * 1024));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DecompressionLayer {
+    limit: usize,
+}
+
+impl Default for DecompressionLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecompressionLayer {
+    /// 默认解压后最大 16 MB
+    pub fn new() -> Self {
+        Self {
+            limit: 16 * 1024 * 1024,
+        }
+    }
+
+    /// 解压后请求体的最大长度
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<S> Layer<S> for DecompressionLayer {
+    type Service = DecompressionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DecompressionService {
+            inner,
+            limit: self.limit,
+        }
+    }
+}
+
+/// [`DecompressionLayer`] 包装后的服务
+#[derive(Debug, Clone)]
+pub struct DecompressionService<S> {
+    inner: S,
+    limit: usize,
+}
+
+impl<S> Service<Request> for DecompressionService<S>
+where
+    S: Service<Request, Response = Response, Error = ResponseError>,
+{
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, mut req: Request) -> Result<Self::Response, Self::Error> {
+        let Some(value) = req.headers.get(&Headers::ContentEncoding.to_string()) else {
+            return self.inner.call(req);
+        };
+        let mut encodings = Vec::new();
+        for coding in value.split(',').map(str::trim) {
+            if coding.is_empty() || coding.eq_ignore_ascii_case("identity") {
+                continue;
+            }
+            match Encoding::from_name(coding) {
+                Some(encoding) => encodings.push(encoding),
+                None => return Ok(unsupported()),
+            }
+        }
+        remove_header(&mut req, Headers::ContentEncoding);
+        if encodings.is_empty() {
+            return self.inner.call(req);
+        }
+
+        if let Some(reader) = req.body_reader.take() {
+            remove_header(&mut req, Headers::ContentLength);
+            let reader = Limited {
+                inner: decode(reader, &encodings),
+                read: 0,
+                limit: self.limit,
+            };
+            req.body_reader = Some(BodyReader::from_reader(reader));
+            return self.inner.call(req);
+        }
+
+        let body = std::mem::take(&mut req.body);
+        let mut data = Vec::new();
+        let result = decode(body.as_slice(), &encodings)
+            .take(self.limit as u64 + 1)
+            .read_to_end(&mut data);
+        if result.is_err() {
+            return Ok(Response::new()
+                .status(StatusCode::BadRequest)
+                .body("请求体解压失败".into()));
+        }
+        if data.len() > self.limit {
+            return Ok(Response::new()
+                .status(StatusCode::PayloadTooLarge)
+                .body(format!("解压后的请求体超过 {} 字节", self.limit).into()));
+        }
+        remove_header(&mut req, Headers::ContentLength);
+        req.headers
+            .notify(&Headers::ContentLength.to_string(), &data.len().to_string());
+        req.body = data;
+        self.inner.call(req)
+    }
+}
+
+/// 多个编码按应用的顺序列出, 解压时反向进行
+fn decode<'a>(reader: impl Read + Send + 'a, encodings: &[Encoding]) -> Box<dyn Read + Send + 'a> {
+    encodings
+        .iter()
+        .rev()
+        .fold(Box::new(reader), |reader, encoding| {
+            encoding.decoder(reader)
+        })
+}
+
+fn remove_header(req: &mut Request, key: Headers) {
+    let key = key.to_string();
+    req.headers.0.retain(|k, _| !k.eq_ignore_ascii_case(&key));
+}
+
+fn unsupported() -> Response {
+    let accept = Encoding::ALL.map(Encoding::as_str).join(", ");
+    Response::new()
+        .status(StatusCode::UnsupportedMediaType)
+        .header(Headers::AcceptEncoding, &accept)
+        .body("不支持的 Content-Encoding".into())
+}
+
+/// 读取超过限制时返回错误
+struct Limited<R> {
+    inner: R,
+    read: usize,
+    limit: usize,
+}
+
+impl<R: Read> Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n;
+        if self.read > self.limit {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("解压后的请求体超过 {} 字节", self.limit),
+            ));
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::DecompressionLayer;
+    use crate::{BodyReader, Request, Router, headers::Headers, middleware::encoding::Encoding};
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn send(router: &mut Router, encoding: &str, body: Vec<u8>) -> String {
+        let mut req = Request::new()
+            .method("POST")
+            .headers(Headers::ContentEncoding, encoding);
+        req.body = body;
+        let resp: Vec<u8> = router.handle(req).into();
+        String::from_utf8(resp).unwrap()
+    }
+
+    #[test]
+    fn test_decompression() {
+        let mut router = Router::new()
+            .route("/", "POST", |body: String| body)
+            .layer(DecompressionLayer::new().limit(1000));
+
+        let resp = send(&mut router, "gzip", gzip(b"hello"));
+        assert!(resp.starts_with("HTTP/1.1 200"));
+        assert!(resp.ends_with("\r\n\r\nhello"));
+
+        let mut br = Vec::new();
+        Encoding::Brotli
+            .encoder(gzip(b"twice").as_slice())
+            .read_to_end(&mut br)
+            .unwrap();
+        let resp = send(&mut router, "gzip, br", br);
+        assert!(resp.ends_with("\r\n\r\ntwice"));
+
+        let resp = send(&mut router, "identity", b"plain".to_vec());
+        assert!(resp.ends_with("\r\n\r\nplain"));
+
+        let resp = send(&mut router, "compress", b"data".to_vec());
+        assert!(resp.starts_with("HTTP/1.1 415"));
+        assert!(resp.contains("Accept-Encoding: br, gzip, deflate\r\n"));
+
+        let resp = send(&mut router, "gzip", gzip(&[0; 1001]));
+        assert!(resp.starts_with("HTTP/1.1 413"));
+
+        let resp = send(&mut router, "gzip", b"not gzip".to_vec());
+        assert!(resp.starts_with("HTTP/1.1 400"));
+    }
+
+    #[test]
+    fn test_content_length_replaced() {
+        let mut router = Router::new()
+            .route("/", "POST", |req: Request| {
+                let lengths: Vec<_> = req
+                    .headers
+                    .0
+                    .iter()
+                    .filter(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+                    .map(|(_, v)| v.clone())
+                    .collect();
+                lengths.join(",")
+            })
+            .layer(DecompressionLayer::new());
+
+        let body = gzip(b"hello");
+        let mut req = Request::new()
+            .method("POST")
+            .headers(Headers::ContentEncoding, "gzip");
+        req.headers
+            .notify("content-length", &body.len().to_string());
+        req.body = body;
+        let resp: Vec<u8> = router.handle(req).into();
+        assert!(String::from_utf8(resp).unwrap().ends_with("\r\n\r\n5"));
+    }
+
+    #[test]
+    fn test_stream_limit() {
+        let mut router = Router::new()
+            .route("/", "POST", |mut req: Request| {
+                match req.take_body_reader().read_all() {
+                    Ok(body) => format!("{} bytes", body.len()),
+                    Err(_) => "too large".to_string(),
+                }
+            })
+            .layer(DecompressionLayer::new().limit(1000));
+
+        for (len, expected) in [(1000, "1000 bytes"), (5000, "too large")] {
+            let body = gzip(&vec![0; len]);
+            let reader = BodyReader::new(body.clone(), std::io::empty(), body.len());
+            let req = Request::new()
+                .method("POST")
+                .headers(Headers::ContentEncoding, "gzip")
+                .body_reader(reader);
+            let resp: Vec<u8> = router.handle(req).into();
+            assert!(String::from_utf8(resp).unwrap().contains(expected));
+        }
+    }
+}
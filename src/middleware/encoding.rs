@@ -0,0 +1,111 @@
+//! 内容编码
+
+use std::io::Read;
+
+use brotli::{CompressorReader, Decompressor};
+use flate2::{
+    Compression,
+    read::{GzEncoder, MultiGzDecoder, ZlibDecoder, ZlibEncoder},
+};
+
+use crate::headers::parse_quality_list;
+
+/// 支持的内容编码, 顺序即服务端的偏好顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Brotli,
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    pub(crate) const ALL: [Encoding; 3] = [Self::Brotli, Self::Gzip, Self::Deflate];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        }
+    }
+
+    /// 解析 `Content-Encoding` 中的一项
+    pub(crate) fn from_name(coding: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.matches(coding))
+    }
+
+    fn matches(self, coding: &str) -> bool {
+        coding.eq_ignore_ascii_case(self.as_str())
+            || (self == Self::Gzip && coding.eq_ignore_ascii_case("x-gzip"))
+    }
+
+    /// 边读取边压缩
+    pub(crate) fn encoder<'a>(self, reader: impl Read + Send + 'a) -> Box<dyn Read + Send + 'a> {
+        match self {
+            Self::Brotli => Box::new(CompressorReader::new(reader, 4096, 5, 22)),
+            Self::Gzip => Box::new(GzEncoder::new(reader, Compression::default())),
+            // HTTP 中的 deflate 是 zlib 格式
+            Self::Deflate => Box::new(ZlibEncoder::new(reader, Compression::default())),
+        }
+    }
+
+    /// 边读取边解压
+    pub(crate) fn decoder<'a>(self, reader: impl Read + Send + 'a) -> Box<dyn Read + Send + 'a> {
+        match self {
+            Self::Brotli => Box::new(Decompressor::new(reader, 4096)),
+            Self::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Self::Deflate => Box::new(ZlibDecoder::new(reader)),
+        }
+    }
+}
+
+/// 按 `Accept-Encoding` 中的权重选择编码, 权重相同时按服务端偏好
+///
+/// 没有列出的编码使用 `*` 的权重, 没有 `*` 时不可接受
+pub(crate) fn negotiate(accept: &str, enabled: &[Encoding]) -> Option<Encoding> {
+    let codings = parse_quality_list(accept);
+
+    let star = codings
+        .iter()
+        .find(|item| item.value == "*")
+        .map(|item| item.quality);
+    let mut best: Option<(Encoding, f32)> = None;
+    for encoding in Encoding::ALL.into_iter().filter(|e| enabled.contains(e)) {
+        let q = codings
+            .iter()
+            .find(|item| encoding.matches(&item.value))
+            .map(|item| item.quality)
+            .or(star)
+            .unwrap_or(0.0);
+        if q > 0.0 && best.is_none_or(|(_, best)| q > best) {
+            best = Some((encoding, q));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encoding, negotiate};
+
+    #[test]
+    fn test_negotiate() {
+        let all = Encoding::ALL;
+        assert_eq!(negotiate("gzip, deflate, br", &all), Some(Encoding::Brotli));
+        assert_eq!(negotiate("gzip, deflate", &all), Some(Encoding::Gzip));
+        assert_eq!(
+            negotiate("br;q=0.5, gzip;q=0.8", &all),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(negotiate("br;q=0, *", &all), Some(Encoding::Gzip));
+        assert_eq!(negotiate("X-GZIP", &all), Some(Encoding::Gzip));
+        assert_eq!(
+            negotiate("*;q=0.1", &[Encoding::Deflate]),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(negotiate("identity", &all), None);
+        assert_eq!(negotiate("gzip;q=0", &all), None);
+        assert_eq!(negotiate("gzip;q=2", &all), None);
+        assert_eq!(negotiate("", &all), None);
+    }
+}
@@ -0,0 +1,100 @@
+//! 使用函数编写中间件
+
+use crate::{
+    error::ResponseError,
+    request::Request,
+    response::{IntoResponse, Response},
+    server::Service,
+};
+
+use super::Layer;
+
+/// 使用函数创建中间件
+///
+/// 函数接收请求和 [`Next`], 调用 `next.run(req)` 把请求交给内层服务,
+/// 不调用时直接返回自己的响应
+///
+/// # Example
+/// ```rust
+/// use http_sv::{
+///     Request, Router,
+///     headers::StatusCode,
+///     middleware::{Next, from_fn},
+///     response::Response,
+/// };
+///
+/// fn auth(req: Request, mut next: Next<'_>) -> Response {
+///     match req.headers.get("Authorization") {
+///         Some("Bearer secret") => next.run(req),
+///         _ => Response::new().status(StatusCode::BadRequest),
+///     }
+/// }
+///
+/// let app = Router::new()
+///     .route("/admin", "GET", "admin")
+///     .route_layer(from_fn(auth))
+///     .route("/", "GET", "index");
+/// ```
+pub fn from_fn<F, R>(f: F) -> FromFnLayer<F>
+where
+    F: Fn(Request, Next<'_>) -> R + Clone + Send + 'static,
+    R: IntoResponse,
+{
+    FromFnLayer { f }
+}
+
+/// [`from_fn`] 创建的 [`Layer`]
+#[derive(Debug, Clone)]
+pub struct FromFnLayer<F> {
+    f: F,
+}
+
+impl<F: Clone, S> Layer<S> for FromFnLayer<F> {
+    type Service = FromFn<F, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FromFn {
+            f: self.f.clone(),
+            inner,
+        }
+    }
+}
+
+/// [`FromFnLayer`] 包装后的服务
+#[derive(Debug, Clone)]
+pub struct FromFn<F, S> {
+    f: F,
+    inner: S,
+}
+
+impl<F, S, R> Service<Request> for FromFn<F, S>
+where
+    F: Fn(Request, Next<'_>) -> R,
+    S: Service<Request, Response = Response, Error = ResponseError>,
+    R: IntoResponse,
+{
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        let next = Next {
+            inner: &mut self.inner,
+        };
+        Ok((self.f)(req, next).into_response())
+    }
+}
+
+/// 中间件的内层服务
+pub struct Next<'a> {
+    inner: &'a mut dyn Service<Request, Response = Response, Error = ResponseError>,
+}
+
+impl Next<'_> {
+    /// 把请求交给内层服务
+    pub fn run(&mut self, req: Request) -> Response {
+        match self.inner.call(req) {
+            Ok(resp) => resp,
+            Err(e) => match e {},
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! 中间件
+//!
+//! [`Layer`] 把一个服务包装为另一个服务, 通过 [`Router::layer`](crate::Router::layer)
+//! 和 [`Router::route_layer`](crate::Router::route_layer) 添加到路由上
+
+mod compression;
+mod conditional;
+mod cors;
+mod decompression;
+mod encoding;
+mod from_fn;
+mod range;
+
+pub use compression::{CompressionLayer, CompressionService};
+pub use conditional::{ConditionalLayer, ConditionalService};
+pub use cors::{AllowOrigin, CorsLayer, CorsService};
+pub use decompression::{DecompressionLayer, DecompressionService};
+pub use from_fn::{FromFn, FromFnLayer, Next, from_fn};
+pub use range::{RangeLayer, RangeService};
+
+/// 包装服务
+///
+/// 多次调用 `layer` 时, 后添加的在外层, 请求先经过后添加的中间件
+pub trait Layer<S> {
+    type Service;
+
+    fn layer(&self, inner: S) -> Self::Service;
+}
@@ -0,0 +1,64 @@
+//! 范围请求中间件
+
+use crate::{
+    error::ResponseError,
+    request::Request,
+    response::{
+        Response,
+        range::{self, RangeRequest},
+    },
+    server::Service,
+};
+
+use super::Layer;
+
+/// 为内存中的响应体处理 `Range` 请求
+///
+/// 只处理 200 响应, 添加 `Accept-Ranges: bytes`, 按 `Range` 和 `If-Range`
+/// 返回 206、`multipart/byteranges` 或 416。
+/// [`ServeDir`](crate::services::ServeDir) 已经自带范围请求支持, 不需要这个中间件
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, middleware::RangeLayer};
+///
+/// let app = Router::new()
+///     .route("/report.csv", "GET", "id,name\n1,bing\n")
+///     .layer(RangeLayer::new());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RangeLayer;
+
+impl RangeLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for RangeLayer {
+    type Service = RangeService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RangeService { inner }
+    }
+}
+
+/// [`RangeLayer`] 包装后的服务
+#[derive(Debug, Clone)]
+pub struct RangeService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for RangeService<S>
+where
+    S: Service<Request, Response = Response, Error = ResponseError>,
+{
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        let range = RangeRequest::from_request(&req);
+        let resp = self.inner.call(req)?;
+        Ok(range::apply(range.as_ref(), resp))
+    }
+}
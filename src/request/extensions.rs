@@ -0,0 +1,65 @@
+//! 请求扩展
+//!
+//! 按类型存储附加在请求上的数据，例如路由参数、共享状态
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+/// 以类型为键的 map, 每种类型最多存储一个值
+#[derive(Default)]
+pub struct Extensions(HashMap<TypeId, Box<dyn Any + Send + Sync>>);
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// 插入一个值, 返回之前同类型的值
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.0
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|old| old.downcast().ok().map(|old| *old))
+    }
+
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.0.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.0.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.0
+            .remove(&TypeId::of::<T>())
+            .and_then(|old| old.downcast().ok().map(|old| *old))
+    }
+}
+
+impl std::fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Extensions;
+
+    #[test]
+    fn test_extensions() {
+        let mut ext = Extensions::new();
+        assert_eq!(ext.insert(1u32), None);
+        assert_eq!(ext.insert(2u32), Some(1));
+        ext.insert("hello".to_string());
+
+        assert_eq!(ext.get::<u32>(), Some(&2));
+        *ext.get_mut::<String>().unwrap() += " world";
+        assert_eq!(ext.remove::<String>().as_deref(), Some("hello world"));
+        assert_eq!(ext.get::<String>(), None);
+    }
+}
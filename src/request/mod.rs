@@ -1,34 +1,63 @@
+mod extensions;
+mod query;
 mod request;
+pub mod target;
 
 use std::{
     collections::HashMap,
-    io::{BufRead, Read},
+    io::{BufRead, Cursor, Read},
     net::TcpStream,
 };
 
+pub use extensions::Extensions;
+pub use query::QueryMap;
 pub use request::Request;
 use request::StartLine;
 
 use crate::{
-    error::RequestError,
-    headers::HttpHeaders,
-    utils::parse::{parse_map, parse_newline, parse_separator, parse_space},
+    error::{RequestError, TargetError},
+    headers::{Headers, HttpHeaders, Mime},
+    utils::parse::{SEPARATOR, parse_map, parse_newline, parse_separator, parse_space},
 };
 
+/// 请求头的最大长度
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+
+/// 默认一次读入内存的请求体的最大长度
+pub(crate) const DEFAULT_MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// 从连接中读取请求
+///
+/// 先读取完整的请求头, 再按 `Content-Length` 读取请求体。
+/// `multipart/form-data` 请求的请求体不会一次读入内存,
+/// 而是作为 [`BodyReader`] 交给提取器按需读取;
+/// 其他请求体超过 2 MB 时返回 [`RequestError::BodyTooLarge`]
 pub fn handle_request(stream: &mut TcpStream) -> Result<Request, RequestError> {
-    let mut buf = [0; 4096];
-    let n = match stream.read(&mut buf) {
-        Ok(n) => {
-            if n == 0 {
-                return Err(RequestError::EmptyRequest);
-            } else {
-                n
-            }
+    read_request(stream, DEFAULT_MAX_BODY_SIZE).map(|(req, _)| req)
+}
+
+/// 读取请求, 同时返回请求体之后已经读到的字节, 协议升级后交给新的协议
+///
+/// 读取请求体之前检查 `Content-Length`, 不是数字时返回 [`RequestError::InvalidContentLength`],
+/// 超过 `max_body_size` 时返回 [`RequestError::BodyTooLarge`]
+pub(crate) fn read_request(
+    stream: &mut TcpStream,
+    max_body_size: usize,
+) -> Result<(Request, Vec<u8>), RequestError> {
+    let mut buf = Vec::with_capacity(4096);
+    let mut chunk = [0; 4096];
+    while find(&buf, SEPARATOR).is_none() {
+        if buf.len() > MAX_HEADER_SIZE {
+            return Err(RequestError::HeaderTooLarge);
         }
-        Err(_) => return Err(RequestError::ReadRequestErr),
-    };
+        match stream.read(&mut chunk) {
+            Ok(0) if buf.is_empty() => return Err(RequestError::EmptyRequest),
+            Ok(0) | Err(_) => return Err(RequestError::ReadRequestErr),
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
 
-    let (header, body) = parse_separator(&buf[..n])?;
+    let (header, body) = parse_separator(&buf)?;
     let (start_line, header_buf) = parse_newline(header)?;
     let (method, other) = parse_space(start_line)?;
     let (path, version) = parse_space(other)?;
@@ -46,19 +75,182 @@ pub fn handle_request(stream: &mut TcpStream) -> Result<Request, RequestError> {
         }
     }
 
+    let method = method.into();
+    let path = std::str::from_utf8(path).map_err(|_| TargetError::InvalidUtf8)?;
+    let (form, path, query) = target::parse_target(method, path)?;
     let start_line = StartLine {
-        method: method.into(),
-        path: String::from_utf8_lossy(path).to_string(),
+        method,
+        form,
+        path,
+        query,
         version: version.into(),
     };
 
     let headers = HttpHeaders(headers);
+    let content_length: usize = match headers.get(&Headers::ContentLength.to_string()) {
+        Some(len) => len
+            .trim()
+            .parse()
+            .map_err(|_| RequestError::InvalidContentLength)?,
+        None => 0,
+    };
+    let mut body = body.to_vec();
+    let leftover = body.split_off(content_length.min(body.len()));
 
-    let req = Request {
+    let mut req = Request {
         start_line,
         headers,
-        body: body.to_vec(),
+        body: Vec::new(),
+        body_reader: None,
+        extensions: Extensions::new(),
+    };
+
+    let multipart = is_multipart(&req);
+    if !multipart && content_length > max_body_size {
+        return Err(RequestError::BodyTooLarge(max_body_size));
+    }
+    let reader = BodyReader::new(body, stream.try_clone()?, content_length);
+    if multipart {
+        req.body_reader = Some(reader);
+    } else {
+        req.body = reader.read_all()?;
+    }
+
+    Ok((req, leftover))
+}
+
+fn is_multipart(req: &Request) -> bool {
+    req.headers
+        .get(&Headers::ContentType.to_string())
+        .is_some_and(|value| {
+            value
+                .trim_start()
+                .to_ascii_lowercase()
+                .starts_with(&Mime::MultipartFormData.to_string())
+        })
+}
+
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// 还没有读取的请求体
+///
+/// 先返回读取请求头时多读到的字节, 再从连接中读取, 总长度不超过 `Content-Length`
+pub struct BodyReader(Box<dyn Read + Send>);
+
+impl BodyReader {
+    pub fn new(buffered: Vec<u8>, rest: impl Read + Send + 'static, len: usize) -> Self {
+        Self(Box::new(Cursor::new(buffered).chain(rest).take(len as u64)))
+    }
+
+    /// 包装任意读取器, 例如解压后的请求体
+    pub fn from_reader(reader: impl Read + Send + 'static) -> Self {
+        Self(Box::new(reader))
+    }
+
+    /// 读取剩余的所有内容
+    pub fn read_all(mut self) -> std::io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        self.0.read_to_end(&mut body)?;
+        Ok(body)
+    }
+}
+
+impl Read for BodyReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl std::fmt::Debug for BodyReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BodyReader").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
     };
 
-    Ok(req)
+    use super::{DEFAULT_MAX_BODY_SIZE, handle_request, read_request};
+    use crate::error::{RequestError, TargetError};
+
+    fn send(parts: &'static [&'static [u8]]) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut client = TcpStream::connect(addr).unwrap();
+            for part in parts {
+                client.write_all(part).unwrap();
+                client.flush().unwrap();
+                thread::sleep(std::time::Duration::from_millis(10));
+            }
+        });
+        listener.accept().unwrap().0
+    }
+
+    #[test]
+    fn test_read_body_across_packets() {
+        let mut stream = send(&[
+            b"POST /a HTTP/1.1\r\nContent-Le",
+            b"ngth: 11\r\n\r\nhello",
+            b" world",
+        ]);
+        let req = handle_request(&mut stream).unwrap();
+        assert_eq!(req.path_ref(), "/a");
+        assert_eq!(req.body_ref(), b"hello world");
+    }
+
+    #[test]
+    fn test_invalid_utf8_target() {
+        let mut stream = send(&[b"GET /caf\xe9 HTTP/1.1\r\nHost: localhost\r\n\r\n"]);
+        assert!(matches!(
+            handle_request(&mut stream),
+            Err(RequestError::TargetError(TargetError::InvalidUtf8))
+        ));
+    }
+
+    #[test]
+    fn test_leftover_bytes() {
+        let mut stream = send(&[b"POST /a HTTP/1.1\r\nContent-Length: 2\r\n\r\nhinext"]);
+        let (req, leftover) = read_request(&mut stream, DEFAULT_MAX_BODY_SIZE).unwrap();
+        assert_eq!(req.body_ref(), b"hi");
+        assert_eq!(leftover, b"next");
+    }
+
+    #[test]
+    fn test_content_length() {
+        let mut stream = send(&[b"POST /a HTTP/1.1\r\nContent-Length: 1x\r\n\r\n"]);
+        assert!(matches!(
+            handle_request(&mut stream),
+            Err(RequestError::InvalidContentLength)
+        ));
+
+        // 超过限制时不读取请求体
+        let mut stream = send(&[b"POST /a HTTP/1.1\r\nContent-Length: 11\r\n\r\nhello"]);
+        assert!(matches!(
+            read_request(&mut stream, 10),
+            Err(RequestError::BodyTooLarge(10))
+        ));
+    }
+
+    #[test]
+    fn test_multipart_body_is_streamed() {
+        let mut stream = send(&[
+            b"POST /upload HTTP/1.1\r\nContent-Type: multipart/form-data; boundary=b\r\nContent-Length: 5\r\n\r\n",
+            b"12345",
+        ]);
+        let mut req = handle_request(&mut stream).unwrap();
+        assert!(req.body_ref().is_empty());
+        let mut body = String::new();
+        req.take_body_reader().read_to_string(&mut body).unwrap();
+        assert_eq!(body, "12345");
+    }
 }
@@ -0,0 +1,57 @@
+//! 查询字符串
+
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+
+use crate::{error::UrlEncodedError, utils::urlencoded};
+
+/// 查询参数, 一个键可以对应多个值
+///
+/// # Example
+/// ```rust
+/// use http_sv::QueryMap;
+///
+/// let query = QueryMap::parse("q=hello%20world&tag=a&tag=b");
+/// assert_eq!(query.get("q"), Some("hello world"));
+/// assert_eq!(query.get_all("tag"), vec!["a", "b"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryMap(pub HashMap<String, Vec<String>>);
+
+impl QueryMap {
+    /// 解析查询字符串(不包括 `?`)
+    pub fn parse(query: &str) -> Self {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for (key, value) in urlencoded::parse(query) {
+            map.entry(key).or_default().push(value);
+        }
+        Self(map)
+    }
+
+    /// 获取第一个值
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key)?.first().map(String::as_str)
+    }
+
+    /// 获取所有值
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        match self.0.get(key) {
+            Some(values) => values.iter().map(String::as_str).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 反序列化为 `T`, 重复的键可以对应 `Vec` 字段
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, UrlEncodedError> {
+        urlencoded::from_map(&self.0)
+    }
+}
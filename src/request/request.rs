@@ -1,10 +1,21 @@
 //! request 请求
 
-use crate::headers::{
-    Headers, HttpHeaders, HttpMethod, HttpVersion, IntoHttpMethod, IntoHttpVersion, read_headers,
+use serde::de::DeserializeOwned;
+
+use crate::{
+    error::{TargetError, UrlEncodedError},
+    headers::{
+        Headers, HttpHeaders, HttpMethod, HttpVersion, IntoHttpMethod, IntoHttpVersion,
+        read_headers,
+    },
+};
+
+use super::{
+    BodyReader, Extensions, QueryMap,
+    target::{TargetForm, parse_target},
 };
 
-#[allow(unused)]
+#[allow(unused, clippy::wrong_self_convention)]
 pub trait IntoRequest {
     fn into_request(&self) -> Request;
 }
@@ -14,6 +25,15 @@ pub struct Request {
     pub start_line: StartLine,
     pub headers: HttpHeaders,
     pub body: Vec<u8>,
+    /// 没有读入 `body` 的请求体, 目前只有 `multipart/form-data` 请求使用
+    pub(crate) body_reader: Option<BodyReader>,
+    pub extensions: Extensions,
+}
+
+impl Default for Request {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Request {
@@ -21,11 +41,15 @@ impl Request {
         Self {
             start_line: StartLine {
                 method: HttpMethod::default(),
+                form: TargetForm::Origin,
                 path: "/".to_string(),
+                query: None,
                 version: HttpVersion::default(),
             },
             headers: HttpHeaders::default(),
             body: Vec::new(),
+            body_reader: None,
+            extensions: Extensions::new(),
         }
     }
 
@@ -38,15 +62,53 @@ impl Request {
         &self.start_line.method
     }
 
-    pub fn path(mut self, path: impl Into<String>) -> Self {
-        self.start_line.path = path.into();
-        self
+    /// 设置请求目标, `?` 之后的部分作为查询字符串
+    ///
+    /// 支持 origin-form 和 absolute-form, CONNECT 和 OPTIONS 需要先设置方法,
+    /// 目标不合法时返回 [`TargetError`]
+    pub fn path(mut self, path: impl Into<String>) -> Result<Self, TargetError> {
+        let (form, path, query) = parse_target(self.start_line.method, &path.into())?;
+        self.start_line.form = form;
+        self.start_line.path = path;
+        self.start_line.query = query;
+        Ok(self)
     }
 
     pub fn path_ref(&self) -> &str {
         &self.start_line.path
     }
 
+    pub fn target_form(&self) -> &TargetForm {
+        &self.start_line.form
+    }
+
+    /// 请求的 authority
+    ///
+    /// absolute-form 和 authority-form 使用请求目标中的值, 否则使用 `Host` 头部
+    pub fn authority(&self) -> Option<&str> {
+        match &self.start_line.form {
+            TargetForm::Absolute { authority, .. } | TargetForm::Authority(authority) => {
+                Some(authority)
+            }
+            _ => self.headers.get(&Headers::Host.to_string()),
+        }
+    }
+
+    /// 原始查询字符串(不包括 `?`)
+    pub fn query_ref(&self) -> Option<&str> {
+        self.start_line.query.as_deref()
+    }
+
+    /// 解析后的查询参数
+    pub fn query_map(&self) -> QueryMap {
+        QueryMap::parse(self.query_ref().unwrap_or_default())
+    }
+
+    /// 把查询参数反序列化为 `T`
+    pub fn query<T: DeserializeOwned>(&self) -> Result<T, UrlEncodedError> {
+        self.query_map().deserialize()
+    }
+
     pub fn version(mut self, version: impl IntoHttpVersion) -> Self {
         self.start_line.version = version.into_http_version();
         self
@@ -70,19 +132,43 @@ impl Request {
         &self.body
     }
 
+    /// 设置流式读取的请求体
+    pub fn body_reader(mut self, reader: BodyReader) -> Self {
+        self.body_reader = Some(reader);
+        self
+    }
+
+    /// 取出流式读取的请求体, 没有时使用 `body` 中的内容
+    pub fn take_body_reader(&mut self) -> BodyReader {
+        match self.body_reader.take() {
+            Some(reader) => reader,
+            None => {
+                let body = std::mem::take(&mut self.body);
+                let len = body.len();
+                BodyReader::new(body, std::io::empty(), len)
+            }
+        }
+    }
+
     pub fn headers(mut self, headers: Headers, value: impl Into<String>) -> Self {
         self.headers.0.insert(headers.to_string(), value.into());
         self
     }
+
+    /// 添加扩展数据
+    pub fn extension<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
 }
 
-impl Into<Vec<u8>> for Request {
-    fn into(self) -> Vec<u8> {
+impl From<Request> for Vec<u8> {
+    fn from(value: Request) -> Self {
         let mut vec = Vec::new();
-        let method: Vec<u8> = self.start_line.method.into();
-        let path: Vec<u8> = self.start_line.path.into();
-        let version: Vec<u8> = self.start_line.version.into();
-        let headers = read_headers(&self.headers.0);
+        let method: Vec<u8> = value.start_line.method.into();
+        let path: Vec<u8> = value.start_line.target().into_bytes();
+        let version: Vec<u8> = value.start_line.version.into();
+        let headers = read_headers(&value.headers.0);
         vec.extend_from_slice(&method);
         vec.extend_from_slice(b" ");
         vec.extend_from_slice(&path);
@@ -91,7 +177,7 @@ impl Into<Vec<u8>> for Request {
         vec.extend_from_slice(b"\r\n");
         vec.extend_from_slice(&headers);
         vec.extend_from_slice(b"\r\n");
-        vec.extend_from_slice(&self.body);
+        vec.extend_from_slice(&value.body);
         vec
     }
 }
@@ -99,25 +185,38 @@ impl Into<Vec<u8>> for Request {
 #[derive(Debug)]
 pub struct StartLine {
     pub method: HttpMethod,
+    pub form: TargetForm,
     pub path: String,
+    pub query: Option<String>,
     pub version: HttpVersion,
 }
 
+impl StartLine {
+    /// 请求行中的请求目标
+    pub fn target(&self) -> String {
+        let path = match &self.query {
+            Some(query) => format!("{}?{}", self.path, query),
+            None => self.path.clone(),
+        };
+        match &self.form {
+            TargetForm::Origin | TargetForm::Asterisk => path,
+            TargetForm::Absolute { scheme, authority } => format!("{scheme}://{authority}{path}"),
+            TargetForm::Authority(authority) => authority.clone(),
+        }
+    }
+}
+
 impl std::fmt::Display for StartLine {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {} {}",
-            self.method.to_string(),
-            self.path,
-            self.version.to_string(),
-        )
+        write!(f, "{} {} {}", self.method, self.target(), self.version)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Request;
+    use serde::Deserialize;
+
+    use super::{Request, TargetError};
 
     #[test]
     fn test1() {
@@ -125,4 +224,62 @@ mod tests {
         let v: Vec<u8> = req.into();
         println!("`{}`", String::from_utf8_lossy(&v));
     }
+
+    #[derive(Debug, Deserialize)]
+    struct Search {
+        q: String,
+        page: Option<u32>,
+    }
+
+    #[test]
+    fn test_query() {
+        let req = Request::new().path("/search?q=hello+world&page=3").unwrap();
+        assert_eq!(req.path_ref(), "/search");
+        assert_eq!(req.query_ref(), Some("q=hello+world&page=3"));
+
+        let search: Search = req.query().unwrap();
+        assert_eq!(search.q, "hello world");
+        assert_eq!(search.page, Some(3));
+
+        let v: Vec<u8> = req.into();
+        assert!(v.starts_with(b"GET /search?q=hello+world&page=3 HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_absolute_form() {
+        let req = Request::new()
+            .path("http://example.com:8080/a?b=1")
+            .unwrap();
+        assert_eq!(req.path_ref(), "/a");
+        assert_eq!(req.query_ref(), Some("b=1"));
+        assert_eq!(req.authority(), Some("example.com:8080"));
+
+        let v: Vec<u8> = req.into();
+        assert!(v.starts_with(b"GET http://example.com:8080/a?b=1 HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_authority_form() {
+        let req = Request::new()
+            .method("CONNECT")
+            .path("example.com:443")
+            .unwrap();
+        assert_eq!(req.path_ref(), "");
+        assert_eq!(req.authority(), Some("example.com:443"));
+
+        let v: Vec<u8> = req.into();
+        assert!(v.starts_with(b"CONNECT example.com:443 HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_invalid_path() {
+        assert!(matches!(
+            Request::new().path("bad"),
+            Err(TargetError::InvalidTarget)
+        ));
+        assert!(matches!(
+            Request::new().method("CONNECT").path("example.com/a"),
+            Err(TargetError::InvalidTarget)
+        ));
+    }
 }
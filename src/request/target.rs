@@ -0,0 +1,248 @@
+//! 请求目标
+//!
+//! 请求行中的目标按 RFC 9112 分为四种形式，
+//! 路由和文件服务使用的路径都要先经过这里规范化:
+//! 百分号解码、处理 `.` 和 `..`、合并重复的 `/`
+
+use crate::{error::TargetError, headers::HttpMethod, utils::percent::percent_decode};
+
+/// 请求目标的形式
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum TargetForm {
+    /// `/where?q=now`
+    #[default]
+    Origin,
+    /// `http://www.example.org/pub/WWW/TheProject.html`, 发往代理的请求使用
+    Absolute { scheme: String, authority: String },
+    /// `www.example.com:80`, 只用于 CONNECT
+    Authority(String),
+    /// `*`, 只用于 OPTIONS
+    Asterisk,
+}
+
+/// 解析请求目标, 返回目标形式、路径和查询字符串
+///
+/// absolute-form 的路径部分和 origin-form 一样参与路由, 路径为空时视为 `/`;
+/// authority-form 的路径为空, asterisk-form 的路径为 `*`
+pub(crate) fn parse_target(
+    method: HttpMethod,
+    target: &str,
+) -> Result<(TargetForm, String, Option<String>), TargetError> {
+    if target.starts_with('/') {
+        let (path, query) = split_query(target);
+        return Ok((TargetForm::Origin, path, query));
+    }
+    if method == HttpMethod::OPTIONS && target == "*" {
+        return Ok((TargetForm::Asterisk, target.to_string(), None));
+    }
+    if method == HttpMethod::CONNECT {
+        if target.is_empty() || target.contains(['/', '?', '@']) {
+            return Err(TargetError::InvalidTarget);
+        }
+        return Ok((
+            TargetForm::Authority(target.to_string()),
+            String::new(),
+            None,
+        ));
+    }
+
+    let (scheme, rest) = target.split_once("://").ok_or(TargetError::InvalidTarget)?;
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    let end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(end);
+    if !valid_scheme || authority.is_empty() {
+        return Err(TargetError::InvalidTarget);
+    }
+
+    let (path, query) = split_query(path);
+    let path = if path.is_empty() {
+        "/".to_string()
+    } else {
+        path
+    };
+    let form = TargetForm::Absolute {
+        scheme: scheme.to_ascii_lowercase(),
+        authority: authority.to_string(),
+    };
+    Ok((form, path, query))
+}
+
+fn split_query(target: &str) -> (String, Option<String>) {
+    match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), Some(query.to_string())),
+        None => (target.to_string(), None),
+    }
+}
+
+/// 路径规范化配置
+#[derive(Debug, Clone)]
+pub struct TargetConfig {
+    /// 是否把 `//` 合并为 `/`, 默认开启
+    pub merge_slashes: bool,
+}
+
+impl Default for TargetConfig {
+    fn default() -> Self {
+        Self {
+            merge_slashes: true,
+        }
+    }
+}
+
+/// 规范化请求路径
+///
+/// 每个路径段单独解码，解码后包含 NUL、`/` 或不是合法 UTF-8 时返回错误,
+/// `..` 不会越过根目录
+///
+/// # Example
+/// ```rust
+/// use http_sv::target::{TargetConfig, normalize_path};
+///
+/// let config = TargetConfig::default();
+/// assert_eq!(normalize_path("/hello%20world", &config).unwrap(), "/hello world");
+/// assert_eq!(normalize_path("/a/../b", &config).unwrap(), "/b");
+/// assert_eq!(normalize_path("//hello", &config).unwrap(), "/hello");
+/// assert_eq!(normalize_path("/../../etc/passwd", &config).unwrap(), "/etc/passwd");
+/// assert!(normalize_path("/a%00b", &config).is_err());
+/// ```
+pub fn normalize_path(path: &str, config: &TargetConfig) -> Result<String, TargetError> {
+    let Some(rest) = path.strip_prefix('/') else {
+        return Err(TargetError::NotAbsolutePath);
+    };
+
+    let mut segments: Vec<String> = Vec::new();
+    // 最后一段是 `.`、`..` 或空时，结果以 `/` 结尾
+    let mut trailing_slash = false;
+    for raw in rest.split('/') {
+        let segment = decode_segment(raw)?;
+        trailing_slash = false;
+        match segment.as_str() {
+            "." => trailing_slash = true,
+            ".." => {
+                segments.pop();
+                trailing_slash = true;
+            }
+            "" if config.merge_slashes => trailing_slash = true,
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut normalized = String::with_capacity(path.len());
+    for segment in &segments {
+        normalized.push('/');
+        normalized.push_str(segment);
+    }
+    if trailing_slash || normalized.is_empty() {
+        normalized.push('/');
+    }
+    Ok(normalized)
+}
+
+fn decode_segment(raw: &str) -> Result<String, TargetError> {
+    if !raw.contains('%') {
+        return Ok(raw.to_string());
+    }
+    let bytes = percent_decode(raw.as_bytes());
+    if bytes.contains(&0) {
+        return Err(TargetError::EncodedNul);
+    }
+    if bytes.contains(&b'/') {
+        return Err(TargetError::EncodedSlash);
+    }
+    String::from_utf8(bytes).map_err(|_| TargetError::InvalidUtf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TargetConfig, TargetForm, normalize_path, parse_target};
+    use crate::{error::TargetError, headers::HttpMethod};
+
+    fn normalize(path: &str) -> Result<String, TargetError> {
+        normalize_path(path, &TargetConfig::default())
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(normalize("/hello%20world").unwrap(), "/hello world");
+        assert_eq!(normalize("/%E4%BD%A0%E5%A5%BD").unwrap(), "/你好");
+    }
+
+    #[test]
+    fn test_dot_segments() {
+        assert_eq!(normalize("/a/../b").unwrap(), "/b");
+        assert_eq!(normalize("/a/./b/.").unwrap(), "/a/b/");
+        assert_eq!(normalize("/a/b/..").unwrap(), "/a/");
+        assert_eq!(normalize("/..").unwrap(), "/");
+        assert_eq!(normalize("/%2e%2E/%2e/secret").unwrap(), "/secret");
+    }
+
+    #[test]
+    fn test_merge_slashes() {
+        assert_eq!(normalize("//hello").unwrap(), "/hello");
+        assert_eq!(normalize("/a//b///").unwrap(), "/a/b/");
+        assert_eq!(normalize("/").unwrap(), "/");
+
+        let config = TargetConfig {
+            merge_slashes: false,
+        };
+        assert_eq!(normalize_path("//hello", &config).unwrap(), "//hello");
+        assert_eq!(normalize_path("/a//../b", &config).unwrap(), "/a/b");
+    }
+
+    #[test]
+    fn test_reject() {
+        assert_eq!(normalize("hello"), Err(TargetError::NotAbsolutePath));
+        assert_eq!(normalize("/a%00"), Err(TargetError::EncodedNul));
+        assert_eq!(normalize("/a%2Fb"), Err(TargetError::EncodedSlash));
+        assert_eq!(normalize("/%FF"), Err(TargetError::InvalidUtf8));
+    }
+
+    #[test]
+    fn test_parse_target() {
+        let (form, path, query) = parse_target(HttpMethod::GET, "/a?b=1").unwrap();
+        assert_eq!(form, TargetForm::Origin);
+        assert_eq!((path.as_str(), query.as_deref()), ("/a", Some("b=1")));
+
+        let (form, path, query) =
+            parse_target(HttpMethod::GET, "HTTP://example.com:8080/a/b?c=1").unwrap();
+        assert_eq!(
+            form,
+            TargetForm::Absolute {
+                scheme: "http".to_string(),
+                authority: "example.com:8080".to_string(),
+            }
+        );
+        assert_eq!((path.as_str(), query.as_deref()), ("/a/b", Some("c=1")));
+
+        let (_, path, query) = parse_target(HttpMethod::GET, "http://example.com?x").unwrap();
+        assert_eq!((path.as_str(), query.as_deref()), ("/", Some("x")));
+
+        let (form, path, _) = parse_target(HttpMethod::CONNECT, "example.com:443").unwrap();
+        assert_eq!(form, TargetForm::Authority("example.com:443".to_string()));
+        assert_eq!(path, "");
+
+        let (form, path, _) = parse_target(HttpMethod::OPTIONS, "*").unwrap();
+        assert_eq!((form, path.as_str()), (TargetForm::Asterisk, "*"));
+    }
+
+    #[test]
+    fn test_parse_target_reject() {
+        for (method, target) in [
+            (HttpMethod::GET, "*"),
+            (HttpMethod::GET, "example.com"),
+            (HttpMethod::GET, "http:///path"),
+            (HttpMethod::GET, "1http://example.com/"),
+            (HttpMethod::CONNECT, "example.com:443/path"),
+            (HttpMethod::CONNECT, ""),
+        ] {
+            assert_eq!(
+                parse_target(method, target),
+                Err(TargetError::InvalidTarget),
+                "{target}"
+            );
+        }
+    }
+}
@@ -1,3 +1,6 @@
 mod response;
 
+pub(crate) mod range;
+pub mod sse;
+
 pub use response::{IntoResponse, Response};
@@ -0,0 +1,326 @@
+//! 范围请求
+//!
+//! 按 RFC 9110 第 14 节处理 `Range` 和 `If-Range`, 生成 206 或 416 响应
+
+use std::{io, ops::Range};
+
+use crate::{
+    headers::{Headers, HttpMethod, StatusCode},
+    request::Request,
+    utils::date,
+};
+
+use super::Response;
+
+/// 一次请求最多处理的范围数量, 超过时忽略 `Range` 返回完整内容
+const MAX_RANGES: usize = 16;
+
+/// 解析 `Range` 的结果
+#[derive(Debug, PartialEq)]
+pub(crate) enum RangeSpec {
+    /// 格式错误或不支持的单位, 返回完整内容
+    Ignore,
+    /// 没有一个范围在内容长度内
+    Unsatisfiable,
+    /// 排序并合并重叠部分后的范围
+    Ranges(Vec<Range<u64>>),
+}
+
+/// 解析 `Range: bytes=0-499, -500`
+pub(crate) fn parse(value: &str, len: u64) -> RangeSpec {
+    let Some((unit, specs)) = value.trim().split_once('=') else {
+        return RangeSpec::Ignore;
+    };
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return RangeSpec::Ignore;
+    }
+
+    let number = |s: &str| {
+        let s = s.trim();
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse::<u64>().ok()
+    };
+    let mut ranges = Vec::new();
+    let specs: Vec<&str> = specs.split(',').filter(|s| !s.trim().is_empty()).collect();
+    if specs.is_empty() || specs.len() > MAX_RANGES {
+        return RangeSpec::Ignore;
+    }
+    for spec in specs {
+        let Some((first, last)) = spec.split_once('-') else {
+            return RangeSpec::Ignore;
+        };
+        if first.trim().is_empty() {
+            let Some(suffix) = number(last) else {
+                return RangeSpec::Ignore;
+            };
+            if suffix > 0 && len > 0 {
+                ranges.push(len.saturating_sub(suffix)..len);
+            }
+            continue;
+        }
+        let Some(first) = number(first) else {
+            return RangeSpec::Ignore;
+        };
+        let last = match last.trim() {
+            "" => u64::MAX,
+            last => match number(last) {
+                Some(last) if last >= first => last,
+                _ => return RangeSpec::Ignore,
+            },
+        };
+        if first < len {
+            ranges.push(first..last.saturating_add(1).min(len));
+        }
+    }
+    if ranges.is_empty() {
+        return RangeSpec::Unsatisfiable;
+    }
+
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    RangeSpec::Ranges(merged)
+}
+
+/// 处理范围请求需要的请求头, 请求体被消耗前取出
+#[derive(Debug, Clone)]
+pub(crate) struct RangeRequest {
+    range: String,
+    if_range: Option<String>,
+}
+
+impl RangeRequest {
+    /// 只有带 `Range` 的 `GET` 请求需要处理
+    pub(crate) fn from_request(req: &Request) -> Option<Self> {
+        if *req.method_ref() != HttpMethod::GET {
+            return None;
+        }
+        Some(Self {
+            range: req.headers.get(&Headers::Range.to_string())?.to_string(),
+            if_range: req
+                .headers
+                .get(&Headers::IfRange.to_string())
+                .map(str::to_string),
+        })
+    }
+
+    /// 解析范围, `If-Range` 不匹配时返回 [`RangeSpec::Ignore`]
+    ///
+    /// `resp` 是完整内容的响应, 用其中的 `ETag` 和 `Last-Modified` 判断 `If-Range`
+    pub(crate) fn spec(&self, resp: &Response, len: u64) -> RangeSpec {
+        if let Some(if_range) = &self.if_range {
+            let headers = resp.headers_ref();
+            let etag = headers.get(&Headers::ETag.to_string());
+            let last_modified = headers.get(&Headers::LastModified.to_string());
+            if !if_range_matches(if_range, etag, last_modified) {
+                return RangeSpec::Ignore;
+            }
+        }
+        parse(&self.range, len)
+    }
+}
+
+/// `If-Range` 中的 ETag 使用强比较, 日期必须和 `Last-Modified` 完全相同
+fn if_range_matches(if_range: &str, etag: Option<&str>, last_modified: Option<&str>) -> bool {
+    let if_range = if_range.trim();
+    if if_range.starts_with("W/") {
+        return false;
+    }
+    if if_range.starts_with('"') {
+        return etag.is_some_and(|etag| !etag.starts_with("W/") && etag.trim() == if_range);
+    }
+    match (date::parse(if_range), last_modified.and_then(date::parse)) {
+        (Some(if_range), Some(last_modified)) => if_range == last_modified,
+        _ => false,
+    }
+}
+
+/// `Content-Range` 的值
+pub(crate) fn content_range(range: &Range<u64>, len: u64) -> String {
+    format!("bytes {}-{}/{len}", range.start, range.end - 1)
+}
+
+/// 把完整内容的响应转为范围响应
+///
+/// `read` 读取内容中的一段, 文件只需要读取请求的部分
+pub(crate) fn respond(
+    resp: Response,
+    spec: RangeSpec,
+    len: u64,
+    mut read: impl FnMut(&Range<u64>) -> io::Result<Vec<u8>>,
+) -> io::Result<Response> {
+    let resp = resp.header(Headers::AcceptRanges, "bytes");
+    let ranges = match spec {
+        RangeSpec::Ignore => return Ok(resp),
+        RangeSpec::Unsatisfiable => {
+            return Ok(resp
+                .status(StatusCode::RangeNotSatisfiable)
+                .header(Headers::ContentRange, &format!("bytes */{len}"))
+                .raw_body(Vec::new()));
+        }
+        RangeSpec::Ranges(ranges) => ranges,
+    };
+    let content_range = |range: &Range<u64>| content_range(range, len);
+
+    let resp = resp.status(StatusCode::PartialContent);
+    if let [range] = ranges.as_slice() {
+        return Ok(resp
+            .header(Headers::ContentRange, &content_range(range))
+            .raw_body(read(range)?));
+    }
+
+    let content_type = resp
+        .headers_ref()
+        .get(&Headers::ContentType.to_string())
+        .map(str::to_string);
+    let boundary = boundary();
+    let mut body = Vec::new();
+    for range in &ranges {
+        body.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+        if let Some(content_type) = &content_type {
+            body.extend_from_slice(format!("Content-Type: {content_type}\r\n").as_bytes());
+        }
+        body.extend_from_slice(
+            format!("Content-Range: {}\r\n\r\n", content_range(range)).as_bytes(),
+        );
+        body.extend_from_slice(&read(range)?);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    Ok(resp
+        .header(
+            Headers::ContentType,
+            &format!("multipart/byteranges; boundary={boundary}"),
+        )
+        .raw_body(body))
+}
+
+/// 处理内存中的响应体, 流式响应体长度未知, 不处理
+pub(crate) fn apply(range: Option<&RangeRequest>, resp: Response) -> Response {
+    if *resp.status_ref() != StatusCode::OK || resp.is_stream() {
+        return resp;
+    }
+    let len = resp.body_ref().len() as u64;
+    let spec = match range {
+        Some(range) => range.spec(&resp, len),
+        None => RangeSpec::Ignore,
+    };
+    if spec == RangeSpec::Ignore {
+        return resp.header(Headers::AcceptRanges, "bytes");
+    }
+    let body = resp.body_ref().to_vec();
+    respond(resp, spec, len, |range| {
+        Ok(body[range.start as usize..range.end as usize].to_vec())
+    })
+    .expect("读取内存中的响应体不会失败")
+}
+
+fn boundary() -> String {
+    let mut bytes = [0; 12];
+    getrandom::getrandom(&mut bytes).expect("无法获取系统随机数");
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RangeRequest, RangeSpec, apply, if_range_matches, parse};
+    use crate::{headers::Headers, request::Request, response::Response};
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_parse() {
+        assert_eq!(parse("bytes=0-499", 1000), RangeSpec::Ranges(vec![0..500]));
+        assert_eq!(
+            parse("bytes=-200", 1000),
+            RangeSpec::Ranges(vec![800..1000])
+        );
+        assert_eq!(
+            parse("bytes=900-", 1000),
+            RangeSpec::Ranges(vec![900..1000])
+        );
+        assert_eq!(
+            parse("bytes=900-5000", 1000),
+            RangeSpec::Ranges(vec![900..1000])
+        );
+        assert_eq!(parse("bytes=-5000", 1000), RangeSpec::Ranges(vec![0..1000]));
+        assert_eq!(
+            parse("bytes=500-599, 0-99, 50-149, 600-700", 1000),
+            RangeSpec::Ranges(vec![0..150, 500..701])
+        );
+        assert_eq!(parse("bytes=1000-", 1000), RangeSpec::Unsatisfiable);
+        assert_eq!(parse("bytes=-0", 1000), RangeSpec::Unsatisfiable);
+        assert_eq!(parse("bytes=0-0", 0), RangeSpec::Unsatisfiable);
+        assert_eq!(parse("bytes=5-1", 1000), RangeSpec::Ignore);
+        assert_eq!(parse("bytes=a-b", 1000), RangeSpec::Ignore);
+        assert_eq!(parse("bytes=+1-2", 1000), RangeSpec::Ignore);
+        assert_eq!(parse("items=0-1", 1000), RangeSpec::Ignore);
+        assert_eq!(
+            parse(&format!("bytes={}", ["0-1"; 17].join(",")), 1000),
+            RangeSpec::Ignore
+        );
+    }
+
+    #[test]
+    fn test_if_range() {
+        let date = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert!(if_range_matches("\"v1\"", Some("\"v1\""), None));
+        assert!(!if_range_matches("\"v1\"", Some("\"v2\""), None));
+        assert!(!if_range_matches("W/\"v1\"", Some("W/\"v1\""), None));
+        assert!(!if_range_matches("\"v1\"", Some("W/\"v1\""), None));
+        assert!(if_range_matches(date, None, Some(date)));
+        assert!(!if_range_matches(
+            date,
+            None,
+            Some("Thu, 22 Oct 2015 07:28:00 GMT")
+        ));
+    }
+
+    fn ranged(range: &str, resp: Response) -> String {
+        let req = Request::new().headers(Headers::Range, range);
+        let range = RangeRequest::from_request(&req);
+        let resp: Vec<u8> = apply(range.as_ref(), resp).into();
+        String::from_utf8(resp).unwrap()
+    }
+
+    #[test]
+    fn test_apply() {
+        let body = || Response::new().raw_body(b"0123456789".to_vec());
+
+        let resp = ranged("bytes=2-4", body());
+        assert!(resp.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+        assert!(resp.contains("Content-Range: bytes 2-4/10\r\n"));
+        assert!(resp.contains("Content-Length: 3\r\n"));
+        assert!(resp.contains("Accept-Ranges: bytes\r\n"));
+        assert!(resp.ends_with("\r\n\r\n234"));
+
+        let resp = ranged("bytes=0-1,8-", body());
+        let boundary = resp
+            .split("boundary=")
+            .nth(1)
+            .unwrap()
+            .split("\r\n")
+            .next()
+            .unwrap()
+            .to_string();
+        assert!(resp.contains("Content-Type: multipart/byteranges; boundary="));
+        assert!(resp.ends_with(&format!(
+            "--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\n01\r\n\
+             --{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 8-9/10\r\n\r\n89\r\n\
+             --{boundary}--\r\n"
+        )));
+
+        let resp = ranged("bytes=20-", body());
+        assert!(resp.starts_with("HTTP/1.1 416"));
+        assert!(resp.contains("Content-Range: bytes */10\r\n"));
+
+        let resp = ranged("bytes=20-", body().status(404u16));
+        assert!(resp.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -1,41 +1,182 @@
-use crate::headers::{
-    Headers, HttpHeaders, HttpVersion, IntoHttpVersion, IntoStatusCode, StatusCode, read_headers,
+use std::{
+    convert::Infallible,
+    io::{self, Read, Write},
 };
 
-#[allow(unused)]
+use crate::{
+    cookie::{Cookie, CookieJar},
+    error::ResponseError,
+    extract::Json,
+    headers::{
+        EntityTag, Headers, HttpHeaders, HttpVersion, IntoHttpVersion, IntoStatusCode, Mime,
+        StatusCode, read_headers,
+    },
+    server::IncomingStream,
+};
+
+/// 转换为响应
+///
+/// 消耗 `self`, 响应体移动到 [`Response`] 中。
+/// `Vec<u8>` 和 `String` 原样使用, 不会复制, 也不会追加换行
 pub trait IntoResponse {
-    fn into_response(&self) -> Response;
+    fn into_response(self) -> Response;
 }
 
 impl IntoResponse for Response {
-    fn into_response(&self) -> Response {
-        (*self).clone()
+    fn into_response(self) -> Response {
+        self
+    }
+}
+
+impl IntoResponse for ResponseError {
+    fn into_response(self) -> Response {
+        match self {}
+    }
+}
+
+impl IntoResponse for Infallible {
+    fn into_response(self) -> Response {
+        match self {}
     }
 }
 
 impl IntoResponse for String {
-    fn into_response(&self) -> Response {
-        Response::new().body(self.as_bytes().to_vec())
+    fn into_response(self) -> Response {
+        Response::new().raw_body(self.into_bytes())
     }
 }
 
 impl IntoResponse for &String {
-    fn into_response(&self) -> Response {
-        Response::new().body(self.as_bytes().to_vec())
+    fn into_response(self) -> Response {
+        Response::new().raw_body(self.as_bytes().to_vec())
     }
 }
 
 impl IntoResponse for &str {
-    fn into_response(&self) -> Response {
-        Response::new().body(self.as_bytes().to_vec())
+    fn into_response(self) -> Response {
+        Response::new().raw_body(self.as_bytes().to_vec())
+    }
+}
+
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> Response {
+        octet_stream(self)
+    }
+}
+
+impl IntoResponse for &'static [u8] {
+    fn into_response(self) -> Response {
+        octet_stream(self.to_vec())
+    }
+}
+
+/// 二进制内容原样发送, 不追加换行
+fn octet_stream(body: Vec<u8>) -> Response {
+    Response::new()
+        .header(
+            Headers::ContentType,
+            &Mime::ApplicationOctetStream.to_string(),
+        )
+        .raw_body(body)
+}
+
+/// 空的 200 响应
+impl IntoResponse for () {
+    fn into_response(self) -> Response {
+        Response::new()
+    }
+}
+
+/// 只有状态码的响应
+impl IntoResponse for StatusCode {
+    fn into_response(self) -> Response {
+        Response::new().status(self)
+    }
+}
+
+/// 序列化为 JSON, 等同于 [`Json`]
+impl IntoResponse for serde_json::Value {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+/// `None` 返回 404
+impl<T: IntoResponse> IntoResponse for Option<T> {
+    fn into_response(self) -> Response {
+        match self {
+            Some(value) => value.into_response(),
+            None => Response::not_found(),
+        }
+    }
+}
+
+/// 使用指定状态码
+impl<B: IntoResponse> IntoResponse for (StatusCode, B) {
+    fn into_response(self) -> Response {
+        let (status, body) = self;
+        body.into_response().status(status)
+    }
+}
+
+/// 使用指定状态码, 并覆盖同名的响应头
+///
+/// `Content-Length` 由响应体决定, 不会被覆盖
+impl<B: IntoResponse> IntoResponse for (StatusCode, HttpHeaders, B) {
+    fn into_response(self) -> Response {
+        let (status, headers, body) = self;
+        let mut resp = body.into_response().status(status);
+        let content_length = Headers::ContentLength.to_string();
+        for (key, value) in headers.0 {
+            if !key.eq_ignore_ascii_case(&content_length) {
+                resp.insert_header(key, value);
+            }
+        }
+        resp
+    }
+}
+
+/// 使用指定状态码, 并覆盖同名的响应头
+impl<B: IntoResponse, const N: usize> IntoResponse for (StatusCode, [(Headers, &str); N], B) {
+    fn into_response(self) -> Response {
+        let (status, headers, body) = self;
+        let resp = body.into_response().status(status);
+        headers
+            .into_iter()
+            .fold(resp, |resp, (key, value)| resp.header(key, value))
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> Response {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(e) => e.into_response(),
+        }
     }
 }
 
-#[derive(Clone)]
 pub struct Response {
     status_line: StatusLine,
     headers: HttpHeaders,
+    /// 可以重复出现的头部, 例如 `Set-Cookie`
+    appended: Vec<(String, String)>,
     body: Vec<u8>,
+    /// 流式响应体, 使用 chunked 编码发送
+    stream: Option<Box<dyn Read + Send>>,
+    /// 流式响应体的长度, 有长度时按 `Content-Length` 原样发送, 否则使用 chunked 编码
+    stream_len: Option<u64>,
+    /// 101 响应写入后接管连接
+    upgrade: Option<OnUpgrade>,
+}
+
+/// 协议升级后接管连接的函数
+pub(crate) type OnUpgrade = Box<dyn FnOnce(IncomingStream) + Send>;
+
+impl Default for Response {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Response {
@@ -46,7 +187,11 @@ impl Response {
                 status: StatusCode::OK,
             },
             headers: HttpHeaders::default(),
+            appended: Vec::new(),
             body: Vec::new(),
+            stream: None,
+            stream_len: None,
+            upgrade: None,
         }
     }
 
@@ -57,7 +202,11 @@ impl Response {
                 status: StatusCode::NotFound,
             },
             headers: HttpHeaders::default(),
+            appended: Vec::new(),
             body: Vec::new(),
+            stream: None,
+            stream_len: None,
+            upgrade: None,
         }
     }
 
@@ -73,7 +222,9 @@ impl Response {
         self
     }
 
-    /// 设置响应主体
+    /// 设置响应主体, 末尾追加 `\r\n`
+    ///
+    /// 不需要换行时使用 [`raw_body`](Self::raw_body)
     pub fn body(mut self, body: Vec<u8>) -> Self {
         self.body = body;
         self.body.extend_from_slice(b"\r\n");
@@ -84,26 +235,210 @@ impl Response {
         self
     }
 
+    /// 原样设置响应体, 不追加换行, 文本、JSON 和二进制内容都使用这个方法
+    pub fn raw_body(mut self, body: Vec<u8>) -> Self {
+        self.headers
+            .0
+            .insert(Headers::ContentLength.to_string(), body.len().to_string());
+        self.body = body;
+        self
+    }
+
+    /// 流式响应体, 边读取边以 chunked 编码发送, 不需要提前知道长度
+    pub fn stream(mut self, reader: impl Read + Send + 'static) -> Self {
+        self.body = Vec::new();
+        self.stream = Some(Box::new(reader));
+        self.stream_len = None;
+        self.remove_header(Headers::ContentLength)
+            .header(Headers::TransferEncoding, "chunked")
+    }
+
+    /// 长度已知的流式响应体, 按 `Content-Length` 原样发送, 例如文件
+    ///
+    /// 最多读取 `len` 字节
+    pub fn sized_stream(mut self, reader: impl Read + Send + 'static, len: u64) -> Self {
+        self.body = Vec::new();
+        self.stream = Some(Box::new(reader.take(len)));
+        self.stream_len = Some(len);
+        self.remove_header(Headers::TransferEncoding)
+            .header(Headers::ContentLength, &len.to_string())
+    }
+
+    /// 替换流式响应体的读取器, 例如添加压缩
+    ///
+    /// 替换后长度未知, 改用 chunked 编码
+    pub(crate) fn map_stream(
+        mut self,
+        f: impl FnOnce(Box<dyn Read + Send>) -> Box<dyn Read + Send>,
+    ) -> Self {
+        let Some(stream) = self.stream.take() else {
+            return self;
+        };
+        self.stream(f(stream))
+    }
+
+    /// 响应写入后把连接交给 `f`, 只对 101 响应生效
+    ///
+    /// `f` 在连接的线程中运行并拥有连接, 服务器不再读写这个连接。
+    /// 请求之后已经读到的字节可以通过 [`IncomingStream::buffered`] 获取,
+    /// 从 [`IncomingStream`] 读取时也会先返回这些字节
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::io::{Read, Write};
+    ///
+    /// use http_sv::{Router, headers::{Headers, StatusCode}, response::Response};
+    ///
+    /// let app = Router::new().route("/echo", "GET", || {
+    ///     Response::new()
+    ///         .status(StatusCode::SwitchingProtocols)
+    ///         .header(Headers::Upgrade, "echo")
+    ///         .header(Headers::Connection, "Upgrade")
+    ///         .on_upgrade(|mut conn| {
+    ///             let mut buf = [0; 1024];
+    ///             while let Ok(n @ 1..) = conn.read(&mut buf) {
+    ///                 if conn.write_all(&buf[..n]).is_err() {
+    ///                     break;
+    ///                 }
+    ///             }
+    ///         })
+    /// });
+    /// ```
+    pub fn on_upgrade(mut self, f: impl FnOnce(IncomingStream) + Send + 'static) -> Self {
+        self.upgrade = Some(Box::new(f));
+        self
+    }
+
+    /// 取出协议升级的处理函数, 不是 101 响应时返回 `None`
+    pub(crate) fn take_upgrade(&mut self) -> Option<OnUpgrade> {
+        match self.status_line.status {
+            StatusCode::SwitchingProtocols => self.upgrade.take(),
+            _ => None,
+        }
+    }
+
+    pub fn is_stream(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    pub fn status_ref(&self) -> &StatusCode {
+        &self.status_line.status
+    }
+
+    pub fn headers_ref(&self) -> &HttpHeaders {
+        &self.headers
+    }
+
+    pub fn body_ref(&self) -> &[u8] {
+        &self.body
+    }
+
     /// 添加响应头
     pub fn header(mut self, key: Headers, value: &str) -> Self {
-        self.headers.0.insert(key.to_string(), value.to_string());
+        self.insert_header(key.to_string(), value.to_string());
+        self
+    }
+
+    /// 设置响应头, 替换大小写不同的同名头部
+    fn insert_header(&mut self, key: String, value: String) {
+        self.headers.0.retain(|k, _| !k.eq_ignore_ascii_case(&key));
+        self.headers.0.insert(key, value);
+    }
+
+    /// 删除响应头
+    pub fn remove_header(mut self, key: Headers) -> Self {
+        let key = key.to_string();
+        self.headers.0.retain(|k, _| !k.eq_ignore_ascii_case(&key));
         self
     }
+
+    /// 设置 `ETag`
+    pub fn etag(self, etag: &EntityTag) -> Self {
+        self.header(Headers::ETag, &etag.to_string())
+    }
+
+    /// 在 `Vary` 中添加一个请求头, 已经存在或为 `*` 时不变
+    pub fn vary(self, name: Headers) -> Self {
+        let name = name.to_string();
+        let vary = match self.headers.get(&Headers::Vary.to_string()) {
+            Some(vary)
+                if vary
+                    .split(',')
+                    .any(|v| v.trim() == "*" || v.trim().eq_ignore_ascii_case(&name)) =>
+            {
+                return self;
+            }
+            Some(vary) => format!("{vary}, {name}"),
+            None => name,
+        };
+        self.header(Headers::Vary, &vary)
+    }
+
+    /// 追加响应头, 不会覆盖同名的头部
+    pub fn append_header(mut self, key: Headers, value: &str) -> Self {
+        self.appended.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// 添加一个 `Set-Cookie` 头部
+    pub fn cookie(self, cookie: &Cookie) -> Self {
+        self.append_header(Headers::SetCookie, &cookie.to_string())
+    }
+
+    /// 把 [`CookieJar`] 中的修改写入 `Set-Cookie` 头部
+    pub fn cookies(self, jar: &CookieJar) -> Self {
+        jar.delta().fold(self, Response::cookie)
+    }
 }
 
-impl Into<Vec<u8>> for Response {
-    fn into(self) -> Vec<u8> {
-        let mut vec = Vec::new();
+impl Response {
+    /// 发送响应, 流式响应体每次读取到的数据作为一个 chunk 立即发送
+    pub fn write_to(self, w: &mut impl Write) -> io::Result<()> {
+        let mut head = Vec::new();
         let version: Vec<u8> = self.status_line.version.into();
         let status: Vec<u8> = self.status_line.status.into();
-        let headers = read_headers(&self.headers.0);
-        vec.extend_from_slice(&version);
-        vec.extend_from_slice(b" ");
-        vec.extend_from_slice(&status);
-        vec.extend_from_slice(b"\r\n");
-        vec.extend_from_slice(&headers);
-        vec.extend_from_slice(b"\r\n");
-        vec.extend_from_slice(&self.body);
+        head.extend_from_slice(&version);
+        head.extend_from_slice(b" ");
+        head.extend_from_slice(&status);
+        head.extend_from_slice(b"\r\n");
+        head.extend_from_slice(&read_headers(&self.headers.0));
+        for (k, v) in &self.appended {
+            head.extend_from_slice(format!("{k}: {v}\r\n").as_bytes());
+        }
+        head.extend_from_slice(b"\r\n");
+        w.write_all(&head)?;
+        w.write_all(&self.body)?;
+
+        let Some(mut stream) = self.stream else {
+            return w.flush();
+        };
+        if self.stream_len.is_some() {
+            io::copy(&mut stream, w)?;
+            return w.flush();
+        }
+        let mut buf = vec![0; 8 * 1024];
+        loop {
+            let n = match stream.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            w.write_all(format!("{n:x}\r\n").as_bytes())?;
+            w.write_all(&buf[..n])?;
+            w.write_all(b"\r\n")?;
+            w.flush()?;
+        }
+        w.write_all(b"0\r\n\r\n")?;
+        w.flush()
+    }
+}
+
+/// 流式响应体会被完整读取, 读取失败时截断
+impl From<Response> for Vec<u8> {
+    fn from(value: Response) -> Self {
+        let mut vec = Vec::new();
+        let _ = value.write_to(&mut vec);
         vec
     }
 }
@@ -115,4 +450,91 @@ pub struct StatusLine {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use std::io::Read;
+
+    use serde_json::json;
+
+    use super::{IntoResponse, Response};
+    use crate::headers::{Headers, HttpHeaders, StatusCode};
+
+    fn text(resp: impl IntoResponse) -> String {
+        let buf: Vec<u8> = resp.into_response().into();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_into_response() {
+        assert!(text(()).starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text(StatusCode::NotFound).starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(text(None::<&str>).starts_with("HTTP/1.1 404"));
+        assert!(text(Some("hi")).ends_with("\r\n\r\nhi"));
+
+        let resp = text(b"\x00\x01".to_vec());
+        assert!(resp.contains("Content-Type: application/octet-stream\r\n"));
+        assert!(resp.contains("Content-Length: 2\r\n"));
+        assert!(resp.ends_with("\r\n\r\n\x00\x01"));
+        let resp = text(&b"bytes"[..]);
+        assert!(resp.contains("Content-Length: 5\r\n"));
+        assert!(resp.ends_with("\r\n\r\nbytes"));
+
+        let resp = text(json!({"ok": true}));
+        assert!(resp.contains("Content-Type: application/json\r\n"));
+        assert!(resp.ends_with("\r\n\r\n{\"ok\":true}"));
+    }
+
+    #[test]
+    fn test_into_response_by_value() {
+        let body = vec![7; 1024];
+        let ptr = body.as_ptr();
+        assert_eq!(body.into_response().body_ref().as_ptr(), ptr);
+
+        let body = "a".repeat(1024);
+        let ptr = body.as_ptr();
+        let resp = body.into_response();
+        assert_eq!(resp.body_ref().as_ptr(), ptr);
+        assert_eq!(resp.body_ref().len(), 1024);
+    }
+
+    #[test]
+    fn test_tuple() {
+        let resp = text((StatusCode::BadRequest, "bad"));
+        assert!(resp.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(resp.ends_with("\r\n\r\nbad"));
+
+        let resp = text((
+            StatusCode::UnprocessableEntity,
+            [(Headers::ContentType, "application/problem+json")],
+            "{}",
+        ));
+        assert!(resp.starts_with("HTTP/1.1 422"));
+        assert!(resp.contains("Content-Type: application/problem+json\r\n"));
+
+        let mut headers = HttpHeaders::new();
+        headers.notify("X-Request-Id", "7");
+        let resp = text((StatusCode::OK, headers, Response::new()));
+        assert!(resp.contains("X-Request-Id: 7\r\n"));
+
+        // 大小写不同的同名头部被替换, Content-Length 保持不变
+        let mut headers = HttpHeaders::new();
+        headers.notify("content-type", "text/html");
+        headers.notify("content-length", "0");
+        let resp = text((StatusCode::OK, headers, "hi"));
+        assert!(resp.contains("content-type: text/html\r\n"));
+        assert!(!resp.contains("Content-Type"));
+        assert!(resp.contains("Content-Length: 2\r\n"));
+    }
+
+    #[test]
+    fn test_stream() {
+        let resp = text(Response::new().stream(b"hello".as_slice().chain(&b" world"[..])));
+        assert!(resp.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(!resp.contains("Content-Length"));
+        assert!(resp.ends_with("\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n"));
+
+        let resp = text(Response::new().sized_stream(&b"hello world"[..], 5));
+        assert!(resp.contains("Content-Length: 5\r\n"));
+        assert!(!resp.contains("Transfer-Encoding"));
+        assert!(resp.ends_with("\r\n\r\nhello"));
+    }
+}
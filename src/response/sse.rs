@@ -0,0 +1,367 @@
+//! 服务器发送事件
+//!
+//! [`Sse`] 以 `text/event-stream` 响应, 连接保持打开, 每个事件写入后立即发送
+
+use std::{
+    convert::Infallible,
+    fmt::Write as _,
+    io::{self, Read},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use crate::{
+    extract::FromRequestParts,
+    headers::{Headers, Mime},
+    request::Request,
+};
+
+use super::{IntoResponse, Response};
+
+/// 一个事件
+///
+/// `event` 和 `id` 中的换行会被删除, `data` 中的每一行写为一个 `data:` 字段
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+    comment: Option<String>,
+}
+
+impl Event {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn data(mut self, data: impl Into<String>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    /// 把值序列化为 JSON 作为 `data`
+    pub fn json_data<T: Serialize>(self, data: &T) -> Result<Self, serde_json::Error> {
+        Ok(self.data(serde_json::to_string(data)?))
+    }
+
+    /// 事件类型, 客户端通过 `addEventListener(event, ..)` 接收
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(single_line(event.into()));
+        self
+    }
+
+    /// 客户端重连时通过 `Last-Event-ID` 带回最后收到的 ID
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        let mut id = single_line(id.into());
+        id.retain(|c| c != '\0');
+        self.id = Some(id);
+        self
+    }
+
+    /// 客户端断开后重连前等待的时间
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// 注释, 客户端会忽略
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = String::new();
+        if let Some(comment) = &self.comment {
+            for line in lines(comment) {
+                let _ = writeln!(buf, ":{line}");
+            }
+        }
+        if let Some(event) = &self.event {
+            let _ = writeln!(buf, "event: {event}");
+        }
+        if let Some(id) = &self.id {
+            let _ = writeln!(buf, "id: {id}");
+        }
+        if let Some(retry) = self.retry {
+            let _ = writeln!(buf, "retry: {}", retry.as_millis());
+        }
+        if let Some(data) = &self.data {
+            for line in lines(data) {
+                let _ = writeln!(buf, "data: {line}");
+            }
+        }
+        buf.push('\n');
+        buf.into_bytes()
+    }
+}
+
+fn single_line(mut value: String) -> String {
+    value.retain(|c| c != '\r' && c != '\n');
+    value
+}
+
+fn lines(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split("\r\n")
+        .flat_map(|line| line.split(['\r', '\n']))
+}
+
+/// 保活注释
+///
+/// 一段时间没有事件时发送一条注释, 防止代理关闭空闲连接
+#[derive(Debug, Clone)]
+pub struct KeepAlive {
+    interval: Duration,
+    text: String,
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeepAlive {
+    /// 默认 15 秒, 注释内容为空
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_secs(15),
+            text: String::new(),
+        }
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self
+    }
+}
+
+enum Source {
+    Iter(Box<dyn Iterator<Item = Event> + Send>),
+    Channel(Receiver<Event>),
+}
+
+/// 服务器发送事件响应
+///
+/// 事件来自迭代器或通道, 迭代器结束或发送端全部关闭时结束响应。
+/// 客户端断开后写入失败, 迭代器或接收端随之释放
+///
+/// # Example
+/// ```rust
+/// use std::{sync::mpsc, thread, time::Duration};
+///
+/// use http_sv::{
+///     Router,
+///     response::sse::{Event, KeepAlive, LastEventId, Sse},
+/// };
+///
+/// fn progress(LastEventId(last): LastEventId) -> Sse {
+///     let start = last.and_then(|id| id.parse::<u32>().ok()).map_or(0, |id| id + 1);
+///     let (tx, rx) = mpsc::channel();
+///     thread::spawn(move || {
+///         for n in start..=100 {
+///             let event = Event::new().id(n.to_string()).event("progress").data(n.to_string());
+///             if tx.send(event).is_err() {
+///                 break;
+///             }
+///             thread::sleep(Duration::from_millis(100));
+///         }
+///     });
+///     Sse::from_channel(rx).keep_alive(KeepAlive::new())
+/// }
+///
+/// let app = Router::new().route("/progress", "GET", progress);
+/// ```
+pub struct Sse {
+    source: Source,
+    keep_alive: Option<KeepAlive>,
+}
+
+impl Sse {
+    pub fn new<I>(events: I) -> Self
+    where
+        I: IntoIterator<Item = Event>,
+        I::IntoIter: Send + 'static,
+    {
+        Self {
+            source: Source::Iter(Box::new(events.into_iter())),
+            keep_alive: None,
+        }
+    }
+
+    pub fn from_channel(rx: Receiver<Event>) -> Self {
+        Self {
+            source: Source::Channel(rx),
+            keep_alive: None,
+        }
+    }
+
+    pub fn keep_alive(mut self, keep_alive: KeepAlive) -> Self {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+}
+
+impl IntoResponse for Sse {
+    fn into_response(self) -> Response {
+        let body = match (self.source, self.keep_alive) {
+            (Source::Iter(events), None) => EventReader::Iter(events),
+            // 迭代器可能阻塞, 放到单独的线程中才能按时发送保活注释
+            (Source::Iter(events), Some(keep_alive)) => {
+                let (tx, rx) = mpsc::sync_channel(0);
+                thread::spawn(move || {
+                    for event in events {
+                        if tx.send(event).is_err() {
+                            break;
+                        }
+                    }
+                });
+                EventReader::Channel(rx, Some(keep_alive))
+            }
+            (Source::Channel(rx), keep_alive) => EventReader::Channel(rx, keep_alive),
+        };
+        Response::new()
+            .header(
+                Headers::ContentType,
+                Mime::TextEventStream.to_string().as_str(),
+            )
+            .header(Headers::CacheControl, "no-cache")
+            .stream(Buffered {
+                events: body,
+                buf: Vec::new(),
+                pos: 0,
+            })
+    }
+}
+
+enum EventReader {
+    Iter(Box<dyn Iterator<Item = Event> + Send>),
+    Channel(Receiver<Event>, Option<KeepAlive>),
+}
+
+impl EventReader {
+    /// 下一个要写入的事件, 结束时返回 `None`
+    fn next(&mut self) -> Option<Event> {
+        match self {
+            Self::Iter(events) => events.next(),
+            Self::Channel(rx, None) => rx.recv().ok(),
+            Self::Channel(rx, Some(keep_alive)) => match rx.recv_timeout(keep_alive.interval) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => Some(Event::new().comment(&keep_alive.text)),
+                Err(RecvTimeoutError::Disconnected) => None,
+            },
+        }
+    }
+}
+
+/// 每次读取返回一个事件, 作为一个 chunk 发送
+struct Buffered {
+    events: EventReader,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for Buffered {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buf.len() {
+            let Some(event) = self.events.next() else {
+                return Ok(0);
+            };
+            self.buf = event.to_bytes();
+            self.pos = 0;
+        }
+        let n = buf.len().min(self.buf.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// 客户端重连时带来的 `Last-Event-ID`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LastEventId(pub Option<String>);
+
+impl FromRequestParts for LastEventId {
+    type Rejection = Infallible;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        let id = req.headers.get(&Headers::LastEventId.to_string());
+        Ok(Self(id.map(str::to_string)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use super::{Event, KeepAlive, LastEventId, Sse};
+    use crate::{Request, Router, headers::Headers, response::IntoResponse};
+
+    fn text(sse: Sse) -> String {
+        let resp: Vec<u8> = sse.into_response().into();
+        String::from_utf8(resp).unwrap()
+    }
+
+    #[test]
+    fn test_event() {
+        let event = Event::new()
+            .event("up\ndate")
+            .id("7")
+            .retry(Duration::from_secs(3))
+            .data("line 1\nline 2\r\nline 3");
+        assert_eq!(
+            String::from_utf8(event.to_bytes()).unwrap(),
+            "event: update\nid: 7\nretry: 3000\ndata: line 1\ndata: line 2\ndata: line 3\n\n"
+        );
+        let event = Event::new().json_data(&[1, 2]).unwrap();
+        assert_eq!(event.to_bytes(), b"data: [1,2]\n\n");
+        assert_eq!(Event::new().comment("ping").to_bytes(), b":ping\n\n");
+    }
+
+    #[test]
+    fn test_sse() {
+        let resp = text(Sse::new([Event::new().data("a"), Event::new().data("b")]));
+        assert!(resp.contains("Content-Type: text/event-stream\r\n"));
+        assert!(resp.contains("Cache-Control: no-cache\r\n"));
+        assert!(resp.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(resp.ends_with("\r\n\r\n9\r\ndata: a\n\n\r\n9\r\ndata: b\n\n\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_keep_alive() {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            tx.send(Event::new().data("done")).unwrap();
+        });
+        let sse = Sse::from_channel(rx).keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_millis(20))
+                .text("ping"),
+        );
+        let resp = text(sse);
+        let ping = resp.find(":ping\n\n").unwrap();
+        assert!(ping < resp.find("data: done\n\n").unwrap());
+        assert!(resp.ends_with("0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_last_event_id() {
+        let mut router = Router::new().route("/", "GET", |LastEventId(id): LastEventId| {
+            Sse::new([Event::new().data(id.unwrap_or_default())])
+        });
+        let req = Request::new().headers(Headers::LastEventId, "42");
+        let resp: Vec<u8> = router.handle(req).into();
+        assert!(String::from_utf8(resp).unwrap().contains("data: 42\n\n"));
+    }
+}
@@ -1,3 +1,3 @@
 mod router;
 
-pub use router::Router;
+pub use router::{Route, Router};
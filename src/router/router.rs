@@ -1,56 +1,438 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use tracing::trace;
 
 use crate::{
     error::{RequestError, ResponseError},
-    handle_request,
-    headers::{HttpMethod, IntoHttpMethod},
-    request::Request,
+    extract::{PathParams, State},
+    handle::{Handler, HandlerService},
+    headers::{Headers, HttpMethod, IntoHttpMethod, StatusCode},
+    middleware::Layer,
+    request::{
+        DEFAULT_MAX_BODY_SIZE, Extensions, Request, read_request,
+        target::{TargetConfig, TargetForm, normalize_path},
+    },
     response::Response,
     server::{IncomingStream, Service},
 };
 
+/// 路由的键, 路径模式和方法
+type RouteKey = (String, HttpMethod);
+
+/// 路由中存储的服务
+type BoxService = Box<dyn CloneService>;
+
+/// 把状态的克隆写入请求扩展
+type StateInjector = Arc<dyn Fn(&mut Extensions) + Send + Sync>;
+
+/// 可以克隆的服务, 使路由可以整体克隆
+trait CloneService: Service<Request, Response = Response, Error = ResponseError> + Send {
+    fn clone_box(&self) -> BoxService;
+}
+
+impl<S> CloneService for S
+where
+    S: Service<Request, Response = Response, Error = ResponseError> + Clone + Send + 'static,
+{
+    fn clone_box(&self) -> BoxService {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for BoxService {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// 路由中的一个服务, [`Layer`] 包装的就是这个类型
+#[derive(Clone)]
+pub struct Route(BoxService);
+
+impl Service<Request> for Route {
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        self.0.call(req)
+    }
+}
+
 /// 路由
+///
+/// 克隆路由会克隆其中所有的服务, 多线程时每个工作线程持有一个克隆
+#[derive(Clone)]
 pub struct Router {
-    path_router: HashMap<
-        (String, HttpMethod),
-        Box<dyn Service<Request, Response = Response, Error = ResponseError>>,
-    >,
+    path_router: HashMap<RouteKey, BoxService>,
+    nested: Vec<(String, Router)>,
+    /// 挂载在前缀下的服务, 处理前缀下所有方法和路径
+    services: Vec<(String, BoxService)>,
+    fallback: Option<BoxService>,
+    /// 路径存在但没有 `OPTIONS` 路由时的默认响应
+    options: BoxService,
+    states: Vec<StateInjector>,
+    target_config: TargetConfig,
+    /// 一次读入内存的请求体的最大长度
+    max_body_size: usize,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Router {
     pub fn new() -> Self {
         Self {
             path_router: HashMap::new(),
+            nested: Vec::new(),
+            services: Vec::new(),
+            fallback: None,
+            options: Box::new(DefaultOptions),
+            states: Vec::new(),
+            target_config: TargetConfig::default(),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
         }
     }
 
-    // 创建并插入
-    pub fn route(
-        mut self,
-        path: &str,
-        method: impl IntoHttpMethod,
-        handle: impl Service<Request, Response = Response, Error = ResponseError> + 'static,
-    ) -> Self {
+    /// 创建并插入
+    ///
+    /// 路径中 `:name` 匹配一个路径段, `*name` 匹配剩余的所有路径段,
+    /// 匹配到的值可以通过 [`Path`](crate::extract::Path) 提取
+    pub fn route<H, T>(mut self, path: &str, method: impl IntoHttpMethod, handle: H) -> Self
+    where
+        H: Handler<T>,
+        T: 'static,
+    {
         self.path_router.insert(
             (path.to_string(), method.into_http_method()),
-            Box::new(handle),
+            Box::new(HandlerService::new(handle)),
         );
         self
     }
 
-    pub fn handle(&mut self, req: Request) -> Response {
-        let path = req.path_ref();
-        let method = req.method_ref();
+    /// 在 `prefix` 下挂载一个子路由
+    ///
+    /// 子路由匹配时使用去掉前缀后的路径，未匹配时优先使用子路由自己的 fallback，
+    /// 没有设置时交给外层路由处理
+    pub fn nest(mut self, prefix: &str, router: Router) -> Self {
+        let prefix = prefix.trim_end_matches('/').to_string();
+        self.nested.push((prefix, router));
+        self
+    }
+
+    /// 在 `prefix` 下挂载一个服务, 例如 [`ServeDir`](crate::services::ServeDir)
+    ///
+    /// 前缀下的所有方法和路径都交给该服务处理, 服务看到的请求路径是去掉前缀后的路径。
+    /// 优先级低于路由和子路由
+    pub fn nest_service<S>(mut self, prefix: &str, service: S) -> Self
+    where
+        S: Service<Request, Response = Response, Error = ResponseError> + Clone + Send + 'static,
+    {
+        let prefix = prefix.trim_end_matches('/').to_string();
+        self.services.push((prefix, Box::new(service)));
+        self
+    }
+
+    /// 设置未匹配到路由时使用的服务，默认返回 404
+    pub fn fallback<H, T>(mut self, handle: H) -> Self
+    where
+        H: Handler<T>,
+        T: 'static,
+    {
+        self.fallback = Some(Box::new(HandlerService::new(handle)));
+        self
+    }
+
+    /// 设置共享状态, 处理器通过 [`State<S>`] 提取
+    ///
+    /// 可以设置多个不同类型的状态, 对子路由同样生效, 子路由可以覆盖同类型的状态
+    pub fn with_state<S>(mut self, state: S) -> Self
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        self.states.push(Arc::new(move |extensions| {
+            extensions.insert(State(state.clone()));
+        }));
+        self
+    }
+
+    /// 为所有路由、子路由和 fallback 添加中间件
+    ///
+    /// 后添加的中间件在外层, 请求按添加顺序的逆序经过各个中间件。
+    /// 没有设置 fallback 时, 默认的 404 响应不经过中间件,
+    /// 默认的 `OPTIONS` 响应经过中间件, 使 CORS 等中间件可以处理预检请求
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone,
+        L::Service:
+            Service<Request, Response = Response, Error = ResponseError> + Clone + Send + 'static,
+    {
+        self = self.route_layer(layer.clone());
+        self.fallback = self.fallback.map(|fallback| wrap(&layer, fallback));
+        self.options = wrap(&layer, self.options);
+        self.services = self
+            .services
+            .into_iter()
+            .map(|(prefix, service)| (prefix, wrap(&layer, service)))
+            .collect();
+        self.nested = self
+            .nested
+            .into_iter()
+            .map(|(prefix, router)| (prefix, router.layer(layer.clone())))
+            .collect();
+        self
+    }
+
+    /// 只为已经添加的路由添加中间件
+    ///
+    /// 之后添加的路由、子路由和 fallback 不受影响,
+    /// 适合鉴权这类不应该把 404 变成其他响应的中间件
+    pub fn route_layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Route>,
+        L::Service:
+            Service<Request, Response = Response, Error = ResponseError> + Clone + Send + 'static,
+    {
+        self.path_router = self
+            .path_router
+            .into_iter()
+            .map(|(key, service)| (key, wrap(&layer, service)))
+            .collect();
+        self
+    }
+
+    /// 是否合并路径中重复的 `/`, 默认开启
+    pub fn merge_slashes(mut self, merge: bool) -> Self {
+        self.target_config.merge_slashes = merge;
+        self
+    }
+
+    /// 请求体的最大长度, 默认 2 MB, 只对作为服务器入口的路由生效
+    ///
+    /// 读取请求体之前按 `Content-Length` 检查, 超过时服务器返回 413;
+    /// `multipart/form-data` 的请求体按需读取, 不受这个限制
+    pub fn max_body_size(mut self, limit: usize) -> Self {
+        self.max_body_size = limit;
+        self
+    }
+
+    /// 处理请求
+    ///
+    /// origin-form 和 absolute-form 按规范化后的路径匹配,
+    /// authority-form 的路径为空, asterisk-form 只能匹配 `*`
+    pub fn handle(&mut self, mut req: Request) -> Response {
         trace!("{}", req.start_line);
-        if let Some(handle) = self.path_router.get_mut(&(path.to_string(), *method)) {
-            let s = handle.call(req);
-            s.unwrap()
+        if matches!(
+            req.target_form(),
+            TargetForm::Origin | TargetForm::Absolute { .. }
+        ) {
+            match normalize_path(req.path_ref(), &self.target_config) {
+                Ok(path) => req.start_line.path = path,
+                Err(e) => {
+                    return Response::new()
+                        .status(StatusCode::BadRequest)
+                        .body(e.to_string().into_bytes());
+                }
+            }
+        }
+        let path = req.path_ref().to_string();
+        match self.dispatch(&path, req, &[]) {
+            Ok(resp) => resp,
+            Err(_) => Response::not_found().body("404 Not Found".into()),
+        }
+    }
+
+    /// 按路径分发请求，没有任何服务处理时把请求交还给调用者
+    ///
+    /// `inherited` 是外层路由的状态, 只在请求被处理时注入,
+    /// 交还的请求不会带上这一层的状态
+    fn dispatch(
+        &mut self,
+        path: &str,
+        mut req: Request,
+        inherited: &[StateInjector],
+    ) -> Result<Response, Box<Request>> {
+        let states: Vec<StateInjector> = inherited.iter().chain(&self.states).cloned().collect();
+        let inject = |req: &mut Request| {
+            for inject in &states {
+                inject(&mut req.extensions);
+            }
+        };
+        let method = *req.method_ref();
+        if let Some(handle) = self.path_router.get_mut(&(path.to_string(), method)) {
+            inject(&mut req);
+            return Ok(handle.call(req).unwrap());
+        }
+
+        if let Some((key, params)) = self.match_pattern(path, method) {
+            inject(&mut req);
+            req.extensions.insert(PathParams(params));
+            let handle = self.path_router.get_mut(&key).unwrap();
+            return Ok(handle.call(req).unwrap());
+        }
+
+        if method == HttpMethod::OPTIONS {
+            let allowed = self.allowed_methods(path);
+            if !allowed.is_empty() {
+                inject(&mut req);
+                req.extensions.insert(AllowedMethods(allowed));
+                return Ok(self.options.call(req).unwrap());
+            }
+        }
+
+        for (prefix, router) in self.nested.iter_mut() {
+            let Some(rest) = strip_prefix(path, prefix) else {
+                continue;
+            };
+            match router.dispatch(rest, req, &states) {
+                Ok(resp) => return Ok(resp),
+                Err(unmatched) => req = *unmatched,
+            }
+        }
+
+        for (prefix, service) in self.services.iter_mut() {
+            if let Some(rest) = strip_prefix(path, prefix) {
+                inject(&mut req);
+                req.start_line.path = rest.to_string();
+                return Ok(service.call(req).unwrap());
+            }
+        }
+
+        match self.fallback.as_mut() {
+            Some(fallback) => {
+                inject(&mut req);
+                Ok(fallback.call(req).unwrap())
+            }
+            None => Err(Box::new(req)),
+        }
+    }
+}
+
+impl Router {
+    /// 查找带参数的路由, 多个路由都能匹配时选择静态路径段最多的
+    fn match_pattern(
+        &self,
+        path: &str,
+        method: HttpMethod,
+    ) -> Option<(RouteKey, Vec<(String, String)>)> {
+        self.path_router
+            .keys()
+            .filter(|(pattern, m)| *m == method && pattern.contains([':', '*']))
+            .filter_map(|(pattern, _)| {
+                let (statics, params) = match_route(pattern, path)?;
+                Some((statics, (pattern.clone(), method), params))
+            })
+            .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.0.cmp(&a.1.0)))
+            .map(|(_, key, params)| (key, params))
+    }
+
+    /// 路径能匹配的路由的方法
+    fn allowed_methods(&self, path: &str) -> Vec<HttpMethod> {
+        let matched: Vec<HttpMethod> = self
+            .path_router
+            .keys()
+            .filter(|(pattern, _)| {
+                pattern == path
+                    || (pattern.contains([':', '*']) && match_route(pattern, path).is_some())
+            })
+            .map(|(_, method)| *method)
+            .collect();
+        METHODS
+            .into_iter()
+            .filter(|method| matched.contains(method))
+            .collect()
+    }
+}
+
+/// `Allow` 中方法的顺序
+const METHODS: [HttpMethod; 9] = [
+    HttpMethod::GET,
+    HttpMethod::HEAD,
+    HttpMethod::POST,
+    HttpMethod::PUT,
+    HttpMethod::PATCH,
+    HttpMethod::DELETE,
+    HttpMethod::CONNECT,
+    HttpMethod::OPTIONS,
+    HttpMethod::TRACE,
+];
+
+/// 路径上注册了路由的方法, 由路由写入请求扩展
+#[derive(Debug, Clone)]
+struct AllowedMethods(Vec<HttpMethod>);
+
+/// 默认的 `OPTIONS` 响应, 在 `Allow` 中列出路径支持的方法
+#[derive(Debug, Clone, Copy)]
+struct DefaultOptions;
+
+impl Service<Request> for DefaultOptions {
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        let mut allow: Vec<String> = req
+            .extensions
+            .get::<AllowedMethods>()
+            .map(|allowed| allowed.0.iter().map(ToString::to_string).collect())
+            .unwrap_or_default();
+        allow.push(HttpMethod::OPTIONS.to_string());
+        Ok(Response::new()
+            .status(StatusCode::NoContent)
+            .header(Headers::Allow, &allow.join(", "))
+            .remove_header(Headers::ContentType))
+    }
+}
+
+fn wrap<L>(layer: &L, service: BoxService) -> BoxService
+where
+    L: Layer<Route>,
+    L::Service:
+        Service<Request, Response = Response, Error = ResponseError> + Clone + Send + 'static,
+{
+    Box::new(layer.layer(Route(service)))
+}
+
+/// 按路径段匹配路由, 返回静态路径段的数量和参数
+fn match_route(pattern: &str, path: &str) -> Option<(usize, Vec<(String, String)>)> {
+    let mut statics = 0;
+    let mut params = Vec::new();
+    let mut segments = path.split('/');
+    for part in pattern.split('/') {
+        if let Some(name) = part.strip_prefix('*') {
+            let rest: Vec<&str> = segments.by_ref().collect();
+            params.push((name.to_string(), rest.join("/")));
+            return Some((statics, params));
+        }
+        let segment = segments.next()?;
+        if let Some(name) = part.strip_prefix(':') {
+            if segment.is_empty() {
+                return None;
+            }
+            params.push((name.to_string(), segment.to_string()));
+        } else if part == segment {
+            statics += 1;
         } else {
-            Response::not_found().body("404 Not Found".into())
+            return None;
         }
     }
+    match segments.next() {
+        Some(_) => None,
+        None => Some((statics, params)),
+    }
+}
+
+/// 按路径段去掉前缀, `/api` 能匹配 `/api` 和 `/api/users`, 但不能匹配 `/apix`
+fn strip_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(prefix)?;
+    if rest.is_empty() {
+        Some("/")
+    } else if rest.starts_with('/') {
+        Some(rest)
+    } else {
+        None
+    }
 }
 
 impl Service<&mut IncomingStream> for Router {
@@ -58,8 +440,319 @@ impl Service<&mut IncomingStream> for Router {
     type Error = RequestError;
     fn call(&mut self, req: &mut IncomingStream) -> Result<Self::Response, Self::Error> {
         let incoming = req;
-        let stream = incoming.stream_mut();
-        let req = handle_request(stream).unwrap();
+        let (req, leftover) = read_request(incoming.stream_mut(), self.max_body_size)?;
+        incoming.buffered = leftover;
         Ok(req)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Router;
+    use crate::{request::Request, response::Response};
+
+    fn body(resp: Response) -> String {
+        let buf: Vec<u8> = resp.into();
+        String::from_utf8_lossy(&buf).to_string()
+    }
+
+    #[test]
+    fn test_default_fallback() {
+        let mut router = Router::new().route("/", "GET", "index");
+        let resp = body(router.handle(Request::new().path("/missing").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 404 Not Found"));
+    }
+
+    #[test]
+    fn test_custom_fallback() {
+        let mut router = Router::new()
+            .route("/", "GET", "index")
+            .fallback("spa index");
+        let resp = body(router.handle(Request::new().path("/app/settings").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 200 OK"));
+        assert!(resp.ends_with("\r\n\r\nspa index"));
+    }
+
+    #[test]
+    fn test_route_ignores_query() {
+        let mut router = Router::new().route("/search", "GET", "search");
+        let resp = body(router.handle(Request::new().path("/search?q=x").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nsearch"));
+    }
+
+    #[test]
+    fn test_route_normalized_path() {
+        let mut router = Router::new().route("/hello world", "GET", "hello");
+        let resp = body(router.handle(Request::new().path("/hello%20world").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nhello"));
+        let resp = body(router.handle(Request::new().path("//a/../hello%20world").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nhello"));
+        let resp = body(router.handle(Request::new().path("/hello%00").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 400 Bad Request"));
+
+        let mut router = Router::new()
+            .merge_slashes(false)
+            .route("/hello", "GET", "hello");
+        let resp = body(router.handle(Request::new().path("//hello").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 404 Not Found"));
+    }
+
+    #[test]
+    fn test_route_target_forms() {
+        let mut router = Router::new()
+            .route("/a", "GET", "a")
+            .route("*", "OPTIONS", "server options")
+            .fallback("fallback");
+        let resp =
+            body(router.handle(Request::new().path("http://example.com//x/../a?q").unwrap()));
+        assert!(resp.ends_with("\r\n\r\na"));
+        let resp = body(router.handle(Request::new().method("OPTIONS").path("*").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nserver options"));
+        let req = Request::new()
+            .method("CONNECT")
+            .path("example.com:443")
+            .unwrap();
+        let resp = body(router.handle(req));
+        assert!(resp.ends_with("\r\n\r\nfallback"));
+    }
+
+    #[test]
+    fn test_default_options() {
+        let mut router = Router::new().route("/users/:id", "GET", "user").route(
+            "/users/:id",
+            "DELETE",
+            "deleted",
+        );
+        let resp = body(router.handle(Request::new().method("OPTIONS").path("/users/7").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(resp.contains("Allow: GET, DELETE, OPTIONS\r\n"));
+        let resp = body(router.handle(Request::new().method("OPTIONS").path("/missing").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_route_params() {
+        use crate::extract::Path;
+
+        let mut router = Router::new()
+            .route("/users/me", "GET", "me")
+            .route("/users/:id", "GET", |Path(id): Path<u32>| {
+                format!("user {id}")
+            })
+            .route(
+                "/users/:id/posts/:post",
+                "GET",
+                |Path((id, post)): Path<(u32, String)>| format!("{id}/{post}"),
+            )
+            .route("/files/*path", "GET", |Path(path): Path<String>| path);
+
+        let resp = body(router.handle(Request::new().path("/users/me").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nme"));
+        let resp = body(router.handle(Request::new().path("/users/42").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nuser 42"));
+        let resp = body(
+            router.handle(
+                Request::new()
+                    .path("/users/42/posts/hello%20world")
+                    .unwrap(),
+            ),
+        );
+        assert!(resp.ends_with("\r\n\r\n42/hello world"));
+        let resp = body(router.handle(Request::new().path("/files/a/b/c.txt").unwrap()));
+        assert!(resp.ends_with("\r\n\r\na/b/c.txt"));
+
+        let resp = body(router.handle(Request::new().path("/users/abc").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 400 Bad Request"));
+        let resp = body(router.handle(Request::new().path("/users/1/2").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 404 Not Found"));
+    }
+
+    #[test]
+    fn test_state() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
+
+        use crate::extract::State;
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let captured = counter.clone();
+        let mut router = Router::new()
+            .route("/count", "GET", move || {
+                captured.fetch_add(1, Ordering::SeqCst).to_string()
+            })
+            .route("/name", "GET", |State(name): State<String>| name)
+            .nest(
+                "/api",
+                Router::new()
+                    .route("/name", "GET", |State(name): State<String>| name)
+                    .with_state("api".to_string()),
+            )
+            .with_state("root".to_string());
+
+        router.handle(Request::new().path("/count").unwrap());
+        let resp = body(router.handle(Request::new().path("/count").unwrap()));
+        assert!(resp.ends_with("\r\n\r\n1"));
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        let resp = body(router.handle(Request::new().path("/name").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nroot"));
+        let resp = body(router.handle(Request::new().path("/api/name").unwrap()));
+        assert!(resp.ends_with("\r\n\r\napi"));
+
+        let mut router = Router::new().route("/", "GET", |State(n): State<u32>| n.to_string());
+        let resp = body(router.handle(Request::new()));
+        assert!(resp.starts_with("HTTP/1.1 500 Internal Server Error"));
+    }
+
+    #[test]
+    fn test_nested_state_not_leaked() {
+        use crate::extract::State;
+
+        let name = |State(name): State<String>| name;
+        let mut router = Router::new()
+            .nest(
+                "/api",
+                Router::new()
+                    .route("/name", "GET", name)
+                    .with_state("api".to_string()),
+            )
+            .nest("/api", Router::new().route("/other", "GET", name))
+            .fallback(name)
+            .with_state("root".to_string());
+
+        let resp = body(router.handle(Request::new().path("/api/name").unwrap()));
+        assert!(resp.ends_with("\r\n\r\napi"));
+        // 子路由没有匹配时, 后面的子路由和 fallback 看到的仍是外层的状态
+        let resp = body(router.handle(Request::new().path("/api/other").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nroot"));
+        let resp = body(router.handle(Request::new().path("/api/missing").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nroot"));
+    }
+
+    #[test]
+    fn test_stateful_service() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
+
+        use crate::{error::ResponseError, server::Service};
+
+        #[derive(Clone, Default)]
+        struct Counter {
+            total: Arc<AtomicUsize>,
+            local: usize,
+        }
+
+        impl Service<Request> for Counter {
+            type Response = String;
+            type Error = ResponseError;
+
+            fn call(&mut self, _req: Request) -> Result<Self::Response, Self::Error> {
+                self.local += 1;
+                let total = self.total.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(format!("{total}/{}", self.local))
+            }
+        }
+
+        fn assert_send<T: Send>(_: &T) {}
+
+        let mut router = Router::new().route("/count", "GET", Counter::default());
+        let mut worker = router.clone();
+        assert_send(&worker);
+
+        router.handle(Request::new().path("/count").unwrap());
+        let resp = body(router.handle(Request::new().path("/count").unwrap()));
+        assert!(resp.ends_with("\r\n\r\n2/2"));
+        // 共享的计数在克隆之间可见, 普通字段每个克隆独立
+        let resp = body(worker.handle(Request::new().path("/count").unwrap()));
+        assert!(resp.ends_with("\r\n\r\n3/1"));
+    }
+
+    #[test]
+    fn test_layer() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::middleware::{Next, from_fn};
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let tag = |name: &'static str| {
+            let log = log.clone();
+            from_fn(move |req: Request, mut next: Next<'_>| {
+                log.lock().unwrap().push(name);
+                next.run(req)
+            })
+        };
+
+        let mut router = Router::new()
+            .route("/", "GET", "index")
+            .nest("/api", Router::new().route("/a", "GET", "a"))
+            .fallback("fallback")
+            .layer(tag("inner"))
+            .layer(tag("outer"));
+
+        let resp = body(router.handle(Request::new()));
+        assert!(resp.ends_with("\r\n\r\nindex"));
+        assert_eq!(*log.lock().unwrap(), ["outer", "inner"]);
+
+        log.lock().unwrap().clear();
+        router.handle(Request::new().path("/api/a").unwrap());
+        router.handle(Request::new().path("/missing").unwrap());
+        assert_eq!(*log.lock().unwrap(), ["outer", "inner", "outer", "inner"]);
+    }
+
+    #[test]
+    fn test_route_layer_short_circuit() {
+        use crate::middleware::{Next, from_fn};
+
+        fn auth(req: Request, mut next: Next<'_>) -> Response {
+            match req.headers.get("authorization") {
+                Some("secret") => next.run(req),
+                _ => Response::new().status(400u16).body("denied".into()),
+            }
+        }
+
+        let mut router = Router::new()
+            .route("/admin", "GET", "admin")
+            .route_layer(from_fn(auth))
+            .route("/", "GET", "index");
+
+        let resp = body(router.handle(Request::new().path("/admin").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 400 Bad Request"));
+        assert!(resp.ends_with("denied\r\n"));
+
+        let mut req = Request::new().path("/admin").unwrap();
+        req.headers.notify("Authorization", "secret");
+        let resp = body(router.handle(req));
+        assert!(resp.ends_with("\r\n\r\nadmin"));
+
+        // 之后添加的路由和 404 不受影响
+        let resp = body(router.handle(Request::new()));
+        assert!(resp.ends_with("\r\n\r\nindex"));
+        let resp = body(router.handle(Request::new().path("/missing").unwrap()));
+        assert!(resp.starts_with("HTTP/1.1 404 Not Found"));
+    }
+
+    #[test]
+    fn test_nested_fallback() {
+        let api = Router::new()
+            .route("/users", "GET", "users")
+            .fallback("api fallback");
+        let mut router = Router::new()
+            .nest("/api", api)
+            .nest("/static", Router::new().route("/a.js", "GET", "a.js"))
+            .fallback("root fallback");
+
+        let resp = body(router.handle(Request::new().path("/api/users").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nusers"));
+        let resp = body(router.handle(Request::new().path("/api/missing").unwrap()));
+        assert!(resp.ends_with("\r\n\r\napi fallback"));
+        // 子路由没有 fallback 时交给外层
+        let resp = body(router.handle(Request::new().path("/static/b.js").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nroot fallback"));
+        let resp = body(router.handle(Request::new().path("/apix").unwrap()));
+        assert!(resp.ends_with("\r\n\r\nroot fallback"));
+    }
+}
@@ -1,8 +1,44 @@
 mod server;
 
-pub use server::{IncomingStream, serve};
+pub use server::{IncomingStream, Server, serve};
 
 /// Service trait
+///
+/// 实现了 `Service<Request>` 的类型可以直接注册到 [`Router`](crate::Router) 上,
+/// `Response` 和 `Error` 都需要实现 [`IntoResponse`](crate::response::IntoResponse)
+///
+/// # 并发
+///
+/// 路由要求服务实现 `Clone + Send`, 每个工作线程持有路由的一个克隆,
+/// 因此 `call` 可以使用 `&mut self`, 但普通字段只在当前克隆内可见。
+/// 需要在所有请求之间共享的数据放在 `Arc` 中, 通过原子类型或 `Mutex` 修改
+///
+/// # Example
+/// ```rust
+/// use std::sync::{
+///     Arc,
+///     atomic::{AtomicUsize, Ordering},
+/// };
+///
+/// use http_sv::{Request, Router, Service, response::Response};
+///
+/// #[derive(Clone, Default)]
+/// struct Counter {
+///     total: Arc<AtomicUsize>,
+/// }
+///
+/// impl Service<Request> for Counter {
+///     type Response = String;
+///     type Error = Response;
+///
+///     fn call(&mut self, _req: Request) -> Result<Self::Response, Self::Error> {
+///         let n = self.total.fetch_add(1, Ordering::Relaxed) + 1;
+///         Ok(format!("request #{n}"))
+///     }
+/// }
+///
+/// let app = Router::new().route("/count", "GET", Counter::default());
+/// ```
 pub trait Service<Request> {
     type Response;
     type Error;
@@ -1,28 +1,68 @@
 use std::{
-    io::Write,
+    io::{Read, Write},
     net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
 };
 
 use crate::{
-    error::ResponseError,
-    headers::Headers,
+    error::{RequestError, ResponseError},
+    headers::{Headers, StatusCode},
     request::Request,
-    response::{IntoResponse, Response},
+    response::Response,
     router::Router,
 };
 
+use tracing::error;
+
 use super::Service;
 
 /// Server
+///
+/// # Example
+/// ```rust,no_run
+/// use std::{net::TcpListener, time::Duration};
+///
+/// use http_sv::{Router, Server};
+///
+/// let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
+/// let app = Router::new().route("/", "GET", "index");
+/// Server::new(listener, app)
+///     .max_connections(256)
+///     .read_timeout(Some(Duration::from_secs(10)))
+///     .start();
+/// ```
 pub struct Server {
     listener: TcpListener,
     service: Router,
+    max_connections: usize,
+    read_timeout: Option<Duration>,
 }
 
 impl Server {
-    /// 创建一个服务器
+    /// 创建一个服务器, 默认最多同时处理 1024 个连接, 读取超时 30 秒
     pub fn new(listener: TcpListener, service: Router) -> Self {
-        Self { listener, service }
+        Self {
+            listener,
+            service,
+            max_connections: 1024,
+            read_timeout: Some(Duration::from_secs(30)),
+        }
+    }
+
+    /// 同时处理的最大连接数, 达到上限时暂停接受新连接
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = max.max(1);
+        self
+    }
+
+    /// 读取请求时的超时, `None` 表示不超时
+    ///
+    /// 客户端在超时时间内没有发送数据时关闭连接, 协议升级后不再生效
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
     }
 
     /// 获取本地地址
@@ -30,30 +70,115 @@ impl Server {
         self.listener.local_addr()
     }
 
+    /// 每个连接在单独的线程中处理, 线程持有路由的一个克隆,
+    /// 流式响应可以长时间占用连接而不阻塞其他连接
     pub fn start(&mut self) {
-        for stream in self.listener.incoming() {
-            if let Ok(stream) = stream {
-                let remote_addr = stream.peer_addr().unwrap();
-                let local_addr = self.local_addr().unwrap();
-
+        let limit = ConnectionLimit::new(self.max_connections);
+        loop {
+            let permit = limit.acquire();
+            let Ok((stream, remote_addr)) = self.listener.accept() else {
+                continue;
+            };
+            if let Err(e) = stream.set_read_timeout(self.read_timeout) {
+                error!("{}: {}", remote_addr, e);
+                continue;
+            }
+            let local_addr = self.local_addr().unwrap();
+            let mut service = self.service.clone();
+            thread::spawn(move || {
+                let _permit = permit;
                 let mut incoming_stream = IncomingStream::new(stream, remote_addr);
-
-                let req = self.service.call(&mut incoming_stream).unwrap();
-                let resp = self
-                    .service
+                let req = match service.call(&mut incoming_stream) {
+                    Ok(req) => req,
+                    Err(e) => {
+                        match error_response(&e) {
+                            Some(resp) => {
+                                if let Err(e) = resp.write_to(incoming_stream.stream_mut()) {
+                                    error!("{}: {}", remote_addr, e);
+                                }
+                            }
+                            None => error!("{}: {}", remote_addr, e),
+                        }
+                        return;
+                    }
+                };
+                let mut resp = service
                     .handle(req)
                     .header(Headers::Host, local_addr.to_string().as_str());
+                let upgrade = resp.take_upgrade();
 
-                let buf: Vec<u8> = resp.into();
-                incoming_stream.stream_mut().write_all(&buf).unwrap();
-            }
+                if let Err(e) = resp.write_to(incoming_stream.stream_mut()) {
+                    error!("{}: {}", remote_addr, e);
+                    return;
+                }
+                // 升级后连接交给新的协议, 服务器不再管理
+                if let Some(upgrade) = upgrade {
+                    if let Err(e) = incoming_stream.stream.set_read_timeout(None) {
+                        error!("{}: {}", remote_addr, e);
+                        return;
+                    }
+                    upgrade(incoming_stream);
+                }
+            });
         }
     }
 }
 
+/// 限制同时处理的连接数, 许可在连接处理结束时归还
+struct ConnectionLimit(Arc<(Mutex<usize>, Condvar)>, usize);
+
+impl ConnectionLimit {
+    fn new(max: usize) -> Self {
+        Self(Arc::new((Mutex::new(0), Condvar::new())), max)
+    }
+
+    /// 连接数达到上限时阻塞, 直到有连接结束
+    fn acquire(&self) -> Permit {
+        let (count, cvar) = &*self.0;
+        let mut count = cvar
+            .wait_while(count.lock().unwrap(), |count| *count >= self.1)
+            .unwrap();
+        *count += 1;
+        Permit(self.0.clone())
+    }
+}
+
+struct Permit(Arc<(Mutex<usize>, Condvar)>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let (count, cvar) = &*self.0;
+        *count.lock().unwrap() -= 1;
+        cvar.notify_one();
+    }
+}
+
+/// 读取请求失败时的响应
+///
+/// 请求目标或 `Content-Length` 不合法时返回 400, 请求体过大时返回 413,
+/// 其他错误直接关闭连接
+fn error_response(e: &RequestError) -> Option<Response> {
+    let status = match e {
+        RequestError::TargetError(_) | RequestError::InvalidContentLength => StatusCode::BadRequest,
+        RequestError::BodyTooLarge(_) => StatusCode::PayloadTooLarge,
+        _ => return None,
+    };
+    Some(
+        Response::new()
+            .status(status)
+            .body(e.to_string().into_bytes()),
+    )
+}
+
+/// 一个客户端连接
+///
+/// 协议升级后作为 [`Response::on_upgrade`] 回调的参数,
+/// 读取时先返回读取请求时多读到的字节, 再从连接中读取
 pub struct IncomingStream {
     pub stream: TcpStream,
     pub remote_addr: std::net::SocketAddr,
+    /// 请求之后已经读到但还没有处理的字节
+    pub(crate) buffered: Vec<u8>,
 }
 
 impl IncomingStream {
@@ -61,9 +186,25 @@ impl IncomingStream {
         Self {
             stream,
             remote_addr,
+            buffered: Vec::new(),
         }
     }
 
+    /// 已经读到但还没有处理的字节
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffered
+    }
+
+    /// 拆分为连接和已经读到的字节
+    pub fn into_parts(self) -> (TcpStream, Vec<u8>) {
+        (self.stream, self.buffered)
+    }
+
+    /// 克隆底层连接, 例如分别用于读和写
+    pub fn try_clone_stream(&self) -> std::io::Result<TcpStream> {
+        self.stream.try_clone()
+    }
+
     pub fn remote_addr(&self) -> SocketAddr {
         self.remote_addr
     }
@@ -73,7 +214,29 @@ impl IncomingStream {
     }
 }
 
-/// 服务启动
+impl Read for IncomingStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.buffered.is_empty() {
+            return self.stream.read(buf);
+        }
+        let n = buf.len().min(self.buffered.len());
+        buf[..n].copy_from_slice(&self.buffered[..n]);
+        self.buffered.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for IncomingStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// 服务启动, 使用 [`Server`] 的默认配置
 pub fn serve(listener: TcpListener, router: Router) {
     let mut server = Server::new(listener, router);
     server.start();
@@ -85,7 +248,7 @@ impl Service<Request> for String {
 
     fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
         let _ = req;
-        let resp = Response::new().body(self.clone().into_bytes());
+        let resp = Response::new().raw_body(self.clone().into_bytes());
         Ok(resp)
     }
 }
@@ -96,23 +259,177 @@ impl Service<Request> for &str {
 
     fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
         let _ = req;
-        let resp = Response::new().body(self.to_string().into_bytes());
+        let resp = Response::new().raw_body(self.to_string().into_bytes());
         Ok(resp)
     }
 }
 
-/// 为闭包实现服务
-impl<F, R> Service<Request> for F
-where
-    F: FnOnce(Request) -> R,
-    F: Copy,
-    R: IntoResponse,
-{
-    type Response = Response;
-    type Error = ResponseError;
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
 
-    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
-        let resp = (self)(req);
-        Ok(resp.into_response())
+    use super::Server;
+    use crate::{
+        Router,
+        headers::{Headers, StatusCode},
+        response::{
+            Response,
+            sse::{Event, Sse},
+        },
+    };
+
+    #[test]
+    fn test_concurrent_connections() {
+        let app = Router::new()
+            .route("/", "GET", "index")
+            .route("/events", "GET", || {
+                let (tx, rx) = mpsc::channel();
+                thread::spawn(move || {
+                    tx.send(Event::new().data("first")).unwrap();
+                    // 保持连接打开
+                    thread::sleep(Duration::from_secs(2));
+                });
+                Sse::from_channel(rx)
+            });
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || super::serve(listener, app));
+
+        let mut events = TcpStream::connect(addr).unwrap();
+        events
+            .write_all(b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut events = BufReader::new(events);
+        let mut line = String::new();
+        while line != "data: first\n" {
+            line.clear();
+            events.read_line(&mut line).unwrap();
+        }
+
+        // 事件流还没有结束, 其他连接仍然可以得到响应
+        let mut index = TcpStream::connect(addr).unwrap();
+        index
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut resp = String::new();
+        index.read_to_string(&mut resp).unwrap();
+        assert!(resp.ends_with("\r\n\r\nindex"));
+    }
+
+    #[test]
+    fn test_invalid_target() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || super::serve(listener, Router::new()));
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET /caf\xe9 HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut resp = String::new();
+        client.read_to_string(&mut resp).unwrap();
+        assert!(resp.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[test]
+    fn test_body_too_large() {
+        let app = Router::new()
+            .route("/", "POST", |body: String| body)
+            .max_body_size(4);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || super::serve(listener, app));
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\n")
+            .unwrap();
+        let mut resp = String::new();
+        client.read_to_string(&mut resp).unwrap();
+        assert!(resp.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: -1\r\n\r\n")
+            .unwrap();
+        let mut resp = String::new();
+        client.read_to_string(&mut resp).unwrap();
+        assert!(resp.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[test]
+    fn test_connection_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/", "GET", "index");
+        thread::spawn(move || {
+            Server::new(listener, app)
+                .max_connections(1)
+                .read_timeout(Some(Duration::from_millis(300)))
+                .start()
+        });
+
+        // 不发送请求的连接占用唯一的名额, 读取超时后被关闭
+        let mut idle = TcpStream::connect(addr).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        let start = Instant::now();
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut resp = String::new();
+        client.read_to_string(&mut resp).unwrap();
+        assert!(resp.ends_with("\r\n\r\nindex"));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(idle.read(&mut [0; 1]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let app = Router::new().route("/upper", "GET", || {
+            Response::new()
+                .status(StatusCode::SwitchingProtocols)
+                .header(Headers::Upgrade, "upper")
+                .header(Headers::Connection, "Upgrade")
+                .on_upgrade(|mut conn| {
+                    let mut buf = [0; 64];
+                    while let Ok(n @ 1..) = conn.read(&mut buf) {
+                        let upper = buf[..n].to_ascii_uppercase();
+                        if conn.write_all(&upper).is_err() {
+                            break;
+                        }
+                    }
+                })
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || super::serve(listener, app));
+
+        // 和请求一起发送的字节不会丢失
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET /upper HTTP/1.1\r\nHost: localhost\r\n\r\nhello")
+            .unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "HTTP/1.1 101 Switching Protocols\r\n");
+        while line != "\r\n" {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"HELLO");
+        client.write_all(b"world").unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"WORLD");
     }
 }
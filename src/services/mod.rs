@@ -0,0 +1,9 @@
+//! 内置服务
+//!
+//! 可以通过 [`Router::route`](crate::Router::route) 或
+//! [`Router::nest_service`](crate::Router::nest_service) 挂载到路由上
+
+mod mime;
+mod serve_dir;
+
+pub use serve_dir::{ServeDir, ServeFile};
@@ -0,0 +1,443 @@
+//! 静态文件
+
+use std::{
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    error::ResponseError,
+    extract::Preconditions,
+    headers::{EntityTag, Headers, HttpMethod, StatusCode},
+    request::Request,
+    response::{
+        Response,
+        range::{self, RangeRequest, RangeSpec},
+    },
+    server::Service,
+    utils::date,
+};
+
+use super::mime;
+
+/// 目录中的静态文件
+///
+/// 请求路径按 `/` 分段映射到 `root` 下的文件, 包含 `..` 的路径和指向目录外的符号链接
+/// 都返回 404。请求目录时返回目录中的 `index.html`, 路径不以 `/` 结尾时先重定向。
+/// 只处理 `GET` 和 `HEAD`, 其他方法返回 405
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, services::ServeDir};
+///
+/// let app = Router::new()
+///     .route("/api/health", "GET", "ok")
+///     .nest_service("/assets", ServeDir::new("dist"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServeDir {
+    root: PathBuf,
+    index: Option<String>,
+}
+
+impl ServeDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            index: Some("index.html".to_string()),
+        }
+    }
+
+    /// 请求目录时返回的文件, 默认为 `index.html`
+    pub fn index_file(mut self, name: impl Into<String>) -> Self {
+        self.index = Some(name.into());
+        self
+    }
+
+    /// 请求目录时返回 404
+    pub fn without_index(mut self) -> Self {
+        self.index = None;
+        self
+    }
+
+    /// 把请求路径映射到 `root` 下, 路径中有 `..` 等特殊路径段时返回 `None`
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let mut full = self.root.clone();
+        for segment in path.split('/').filter(|s| !s.is_empty() && *s != ".") {
+            let mut components = Path::new(segment).components();
+            let normal = matches!(components.next(), Some(Component::Normal(_)))
+                && components.next().is_none()
+                && !segment.contains(['\\', '\0']);
+            if !normal {
+                return None;
+            }
+            full.push(segment);
+        }
+        Some(full)
+    }
+
+    fn serve(&self, req: &Request) -> Response {
+        let Some(mut path) = self.resolve(req.path_ref()) else {
+            return not_found();
+        };
+        if path.is_dir() {
+            let request_path = req.path_ref();
+            if !request_path.ends_with('/') {
+                // 使用相对路径, 不需要知道挂载的前缀
+                let name = request_path.rsplit('/').next().unwrap_or_default();
+                return Response::new()
+                    .status(StatusCode::MovedPermanently)
+                    .header(Headers::Location, &format!("{name}/"));
+            }
+            match &self.index {
+                Some(index) => path.push(index),
+                None => return not_found(),
+            }
+        }
+        serve_file(Some(&self.root), &path, req)
+    }
+}
+
+impl Service<Request> for ServeDir {
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        match check_method(&req) {
+            Some(resp) => Ok(resp),
+            None => Ok(self.serve(&req)),
+        }
+    }
+}
+
+/// 单个静态文件, 忽略请求路径
+///
+/// # Example
+/// ```rust
+/// use http_sv::{Router, services::ServeFile};
+///
+/// let app = Router::new().route("/favicon.ico", "GET", ServeFile::new("dist/favicon.ico"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServeFile {
+    path: PathBuf,
+}
+
+impl ServeFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl Service<Request> for ServeFile {
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, req: Request) -> Result<Self::Response, Self::Error> {
+        match check_method(&req) {
+            Some(resp) => Ok(resp),
+            None => Ok(serve_file(None, &self.path, &req)),
+        }
+    }
+}
+
+/// 不是 `GET` 或 `HEAD` 时返回 405
+fn check_method(req: &Request) -> Option<Response> {
+    match req.method_ref() {
+        HttpMethod::GET | HttpMethod::HEAD => None,
+        _ => Some(
+            Response::new()
+                .status(StatusCode::MethodNotAllowed)
+                .header(Headers::Allow, "GET, HEAD"),
+        ),
+    }
+}
+
+/// 读取文件, 设置了 `root` 时文件的真实路径必须在 `root` 下
+///
+/// 范围请求只读取请求的部分
+fn serve_file(root: Option<&Path>, path: &Path, req: &Request) -> Response {
+    if let Some(root) = root {
+        let inside = match (root.canonicalize(), path.canonicalize()) {
+            (Ok(root), Ok(path)) => path.starts_with(root),
+            _ => false,
+        };
+        if !inside {
+            return not_found();
+        }
+    }
+    let Ok(metadata) = fs::metadata(path) else {
+        return not_found();
+    };
+    if !metadata.is_file() {
+        return not_found();
+    }
+
+    let len = metadata.len();
+    let mut resp = Response::new().header(Headers::ContentType, mime::from_path(path));
+    if let Ok(modified) = metadata.modified() {
+        // 修改时间和长度不能保证内容逐字节相同, 使用弱 ETag
+        let nanos = modified
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos());
+        resp = resp
+            .header(Headers::LastModified, &date::format(modified.into()))
+            .etag(&EntityTag::weak(format!("{nanos:x}-{len:x}")));
+    }
+    let resp = Preconditions::from_request(req).apply(req.method_ref(), resp);
+    if *resp.status_ref() != StatusCode::OK {
+        return resp;
+    }
+    if *req.method_ref() == HttpMethod::HEAD {
+        return resp
+            .header(Headers::AcceptRanges, "bytes")
+            .header(Headers::ContentLength, &len.to_string());
+    }
+
+    let spec = match RangeRequest::from_request(req) {
+        Some(range) => range.spec(&resp, len),
+        None => RangeSpec::Ignore,
+    };
+    let Ok(mut file) = File::open(path) else {
+        return not_found();
+    };
+    // 完整内容和单个范围直接从文件流式发送, 不读入内存
+    let single = match &spec {
+        RangeSpec::Ignore => Some(0..len),
+        RangeSpec::Ranges(ranges) if ranges.len() == 1 => Some(ranges[0].clone()),
+        _ => None,
+    };
+    if let Some(range) = single {
+        if file.seek(SeekFrom::Start(range.start)).is_err() {
+            return not_found();
+        }
+        let resp = resp.header(Headers::AcceptRanges, "bytes");
+        let resp = match spec {
+            RangeSpec::Ignore => resp,
+            _ => resp
+                .status(StatusCode::PartialContent)
+                .header(Headers::ContentRange, &range::content_range(&range, len)),
+        };
+        return resp.sized_stream(file, range.end - range.start);
+    }
+    let read = |range: &std::ops::Range<u64>| {
+        let mut data = vec![0; (range.end - range.start) as usize];
+        file.seek(SeekFrom::Start(range.start))?;
+        file.read_exact(&mut data)?;
+        Ok(data)
+    };
+    range::respond(resp, spec, len, read).unwrap_or_else(|_| not_found())
+}
+
+fn not_found() -> Response {
+    Response::not_found().body("404 Not Found".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::{ServeDir, ServeFile};
+    use crate::{Request, Router, headers::Headers};
+
+    fn root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("http_sv-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("public/docs")).unwrap();
+        fs::write(root.join("public/index.html"), "<h1>home</h1>").unwrap();
+        fs::write(root.join("public/app.js"), "console.log(1)").unwrap();
+        fs::write(root.join("public/docs/index.html"), "docs").unwrap();
+        fs::write(root.join("secret.txt"), "secret").unwrap();
+        root
+    }
+
+    fn send(router: &mut Router, req: Request) -> String {
+        let resp: Vec<u8> = router.handle(req).into();
+        String::from_utf8(resp).unwrap()
+    }
+
+    #[test]
+    fn test_serve_dir() {
+        let root = root("serve-dir");
+        let mut router = Router::new().nest_service("/static", ServeDir::new(root.join("public")));
+
+        let resp = send(&mut router, Request::new().path("/static/app.js").unwrap());
+        assert!(resp.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(resp.contains("Content-Type: text/javascript; charset=utf-8\r\n"));
+        assert!(resp.contains("Content-Length: 14\r\n"));
+        assert!(resp.contains("Last-Modified: "));
+        assert!(resp.ends_with("\r\n\r\nconsole.log(1)"));
+
+        let resp = send(&mut router, Request::new().path("/static/").unwrap());
+        assert!(resp.ends_with("<h1>home</h1>"));
+        let resp = send(&mut router, Request::new().path("/static").unwrap());
+        assert!(resp.ends_with("<h1>home</h1>"));
+
+        let resp = send(&mut router, Request::new().path("/static/docs").unwrap());
+        assert!(resp.starts_with("HTTP/1.1 301"));
+        assert!(resp.contains("Location: docs/\r\n"));
+        let resp = send(&mut router, Request::new().path("/static/docs/").unwrap());
+        assert!(resp.ends_with("docs"));
+
+        let resp = send(
+            &mut router,
+            Request::new()
+                .method("HEAD")
+                .path("/static/app.js")
+                .unwrap(),
+        );
+        assert!(resp.contains("Content-Length: 14\r\n"));
+        assert!(resp.ends_with("\r\n\r\n"));
+
+        let resp = send(
+            &mut router,
+            Request::new()
+                .method("POST")
+                .path("/static/app.js")
+                .unwrap(),
+        );
+        assert!(resp.starts_with("HTTP/1.1 405"));
+        assert!(resp.contains("Allow: GET, HEAD\r\n"));
+
+        for path in [
+            "/static/missing.js",
+            "/static/../secret.txt",
+            "/static/%2e%2e/secret.txt",
+        ] {
+            let resp = send(&mut router, Request::new().path(path).unwrap());
+            assert!(resp.starts_with("HTTP/1.1 404"), "{path}");
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_traversal() {
+        let root = root("traversal");
+        let dir = ServeDir::new(root.join("public"));
+        assert!(dir.resolve("/../secret.txt").is_none());
+        assert!(dir.resolve("/docs/..").is_none());
+        assert!(dir.resolve("/a\\..\\b").is_none());
+        assert_eq!(
+            dir.resolve("/./docs//index.html"),
+            Some(root.join("public/docs/index.html"))
+        );
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("secret.txt"), root.join("public/link.txt"))
+                .unwrap();
+            let mut router = Router::new().nest_service("/", ServeDir::new(root.join("public")));
+            let resp = send(&mut router, Request::new().path("/link.txt").unwrap());
+            assert!(resp.starts_with("HTTP/1.1 404"));
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_range() {
+        let root = root("range");
+        let mut router = Router::new().nest_service("/", ServeDir::new(root.join("public")));
+
+        let req = Request::new()
+            .path("/app.js")
+            .unwrap()
+            .headers(Headers::Range, "bytes=-3");
+        let resp = send(&mut router, req);
+        assert!(resp.starts_with("HTTP/1.1 206"));
+        assert!(resp.contains("Content-Range: bytes 11-13/14\r\n"));
+        assert!(resp.ends_with("\r\n\r\n(1)"));
+
+        // 完整内容和单个范围都从文件流式读取
+        assert!(
+            router
+                .handle(Request::new().path("/app.js").unwrap())
+                .is_stream()
+        );
+        let req = Request::new()
+            .path("/app.js")
+            .unwrap()
+            .headers(Headers::Range, "bytes=0-");
+        let resp = router.handle(req);
+        assert!(resp.is_stream());
+        let resp: Vec<u8> = resp.into();
+        let resp = String::from_utf8(resp).unwrap();
+        assert!(resp.starts_with("HTTP/1.1 206"));
+        assert!(resp.contains("Content-Length: 14\r\n"));
+        assert!(resp.contains("Content-Range: bytes 0-13/14\r\n"));
+        assert!(resp.ends_with("\r\n\r\nconsole.log(1)"));
+
+        let req = Request::new()
+            .path("/app.js")
+            .unwrap()
+            .headers(Headers::Range, "bytes=0-6")
+            .headers(Headers::IfRange, "Wed, 21 Oct 2015 07:28:00 GMT");
+        let resp = send(&mut router, req);
+        assert!(resp.starts_with("HTTP/1.1 200"));
+        assert!(resp.contains("Accept-Ranges: bytes\r\n"));
+
+        let req = Request::new()
+            .path("/app.js")
+            .unwrap()
+            .headers(Headers::Range, "bytes=14-");
+        assert!(send(&mut router, req).starts_with("HTTP/1.1 416"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_conditional() {
+        let root = root("conditional");
+        let mut router = Router::new().nest_service("/", ServeDir::new(root.join("public")));
+
+        let resp = send(&mut router, Request::new().path("/app.js").unwrap());
+        let header = |name: &str| {
+            resp.split(&format!("{name}: "))
+                .nth(1)
+                .unwrap()
+                .split("\r\n")
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        let (etag, last_modified) = (header("ETag"), header("Last-Modified"));
+        assert!(etag.starts_with("W/\""));
+
+        let req = Request::new()
+            .path("/app.js")
+            .unwrap()
+            .headers(Headers::IfNoneMatch, etag.as_str());
+        let resp = send(&mut router, req);
+        assert!(resp.starts_with("HTTP/1.1 304"));
+        assert!(resp.ends_with("\r\n\r\n"));
+
+        let req = Request::new()
+            .method("HEAD")
+            .path("/app.js")
+            .unwrap()
+            .headers(Headers::IfModifiedSince, last_modified.as_str());
+        assert!(send(&mut router, req).starts_with("HTTP/1.1 304"));
+
+        // 弱 ETag 不能通过 If-Match 的强比较
+        let req = Request::new()
+            .path("/app.js")
+            .unwrap()
+            .headers(Headers::IfMatch, etag.as_str());
+        assert!(send(&mut router, req).starts_with("HTTP/1.1 412"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_serve_file() {
+        let root = root("serve-file");
+        let mut router = Router::new().route(
+            "/home",
+            "GET",
+            ServeFile::new(root.join("public/index.html")),
+        );
+        let resp = send(&mut router, Request::new().path("/home").unwrap());
+        assert!(resp.contains("Content-Type: text/html; charset=utf-8\r\n"));
+        assert!(resp.ends_with("<h1>home</h1>"));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
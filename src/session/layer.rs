@@ -0,0 +1,413 @@
+//! 会话中间件
+
+use std::sync::Arc;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+
+use crate::{
+    cookie::{Cookie, CookieJar, SameSite},
+    error::{ResponseError, SessionError},
+    headers::{Headers, StatusCode},
+    middleware::Layer,
+    request::Request,
+    response::{IntoResponse, Response},
+    server::Service,
+};
+
+use super::{Session, SessionRecord, SessionStore};
+
+#[derive(Debug, Clone)]
+struct SessionConfig {
+    cookie_name: String,
+    secure: bool,
+    idle_timeout: Option<Duration>,
+    absolute_timeout: Option<Duration>,
+    rotation: Option<Duration>,
+}
+
+impl SessionConfig {
+    fn expired(&self, record: &SessionRecord, now: i64) -> bool {
+        let passed = |since: i64, timeout: Option<Duration>| {
+            timeout.is_some_and(|timeout| now - since >= timeout.num_seconds())
+        };
+        passed(record.accessed_at, self.idle_timeout)
+            || passed(record.created_at, self.absolute_timeout)
+    }
+
+    fn cookie(&self, id: String, record: &SessionRecord, now: i64) -> Cookie {
+        let cookie = Cookie::new(self.cookie_name.clone(), id)
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .secure(self.secure);
+        let absolute = self
+            .absolute_timeout
+            .map(|timeout| timeout - Duration::seconds(now - record.created_at));
+        match [self.idle_timeout, absolute].into_iter().flatten().min() {
+            Some(max_age) => cookie.max_age(max_age),
+            None => cookie,
+        }
+    }
+}
+
+/// 会话中间件
+///
+/// 会话 ID 保存在 `HttpOnly`、`SameSite=Lax`、`Path=/` 的 cookie 中,
+/// 没有写入数据的新会话不会保存。默认不过期, 可以分别设置:
+///
+/// - [`idle_timeout`](Self::idle_timeout): 超过这段时间没有访问则过期, 每次访问都会刷新 cookie
+/// - [`absolute_timeout`](Self::absolute_timeout): 从创建开始超过这段时间则过期
+/// - [`rotate_every`](Self::rotate_every): 定期更换会话 ID, 数据保留
+///
+/// # Example
+/// ```rust
+/// use chrono::Duration;
+/// use http_sv::{
+///     Router,
+///     session::{MemoryStore, SessionLayer},
+/// };
+///
+/// let sessions = SessionLayer::new(MemoryStore::new())
+///     .cookie_name("sid")
+///     .secure(true)
+///     .idle_timeout(Duration::minutes(30))
+///     .absolute_timeout(Duration::days(1))
+///     .rotate_every(Duration::hours(1));
+///
+/// let app = Router::new().route("/", "GET", "index").layer(sessions);
+/// ```
+pub struct SessionLayer<St> {
+    store: Arc<St>,
+    config: SessionConfig,
+}
+
+impl<St> Clone for SessionLayer<St> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<St: SessionStore> SessionLayer<St> {
+    pub fn new(store: St) -> Self {
+        Self {
+            store: Arc::new(store),
+            config: SessionConfig {
+                cookie_name: "session_id".to_string(),
+                secure: false,
+                idle_timeout: None,
+                absolute_timeout: None,
+                rotation: None,
+            },
+        }
+    }
+
+    /// cookie 名称, 默认为 `session_id`
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.config.cookie_name = name.into();
+        self
+    }
+
+    /// 只通过 HTTPS 发送 cookie
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.config.secure = secure;
+        self
+    }
+
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.config.idle_timeout = Some(timeout);
+        self
+    }
+
+    pub fn absolute_timeout(mut self, timeout: Duration) -> Self {
+        self.config.absolute_timeout = Some(timeout);
+        self
+    }
+
+    pub fn rotate_every(mut self, interval: Duration) -> Self {
+        self.config.rotation = Some(interval);
+        self
+    }
+}
+
+impl<St, S> Layer<S> for SessionLayer<St> {
+    type Service = SessionService<St, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionService {
+            store: self.store.clone(),
+            config: self.config.clone(),
+            inner,
+        }
+    }
+}
+
+/// [`SessionLayer`] 包装后的服务
+pub struct SessionService<St, S> {
+    store: Arc<St>,
+    config: SessionConfig,
+    inner: S,
+}
+
+impl<St, S: Clone> Clone for SessionService<St, S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            config: self.config.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<St, S> SessionService<St, S>
+where
+    St: SessionStore,
+{
+    fn load(&self, id: &str, now: i64) -> Result<Option<SessionRecord>, SessionError> {
+        match self.store.load(id)? {
+            Some(record) if self.config.expired(&record, now) => {
+                self.store.delete(id)?;
+                Ok(None)
+            }
+            record => Ok(record),
+        }
+    }
+
+    /// 保存会话, 返回需要设置的 cookie
+    fn save(
+        &self,
+        session: &Session,
+        has_cookie: bool,
+        now: i64,
+    ) -> Result<Option<Cookie>, SessionError> {
+        let mut inner = session.lock();
+        let removal = || Cookie::removal(self.config.cookie_name.clone()).path("/");
+
+        if inner.destroyed {
+            if let Some(id) = &inner.id {
+                self.store.delete(id)?;
+            }
+            return Ok(has_cookie.then(removal));
+        }
+        if inner.id.is_none() && inner.record.data.is_empty() {
+            // cookie 中的会话已经过期或不存在
+            return Ok(has_cookie.then(removal));
+        }
+
+        let rotate = inner.rotate
+            || self
+                .config
+                .rotation
+                .is_some_and(|interval| now - inner.record.rotated_at >= interval.num_seconds());
+        let id = match inner.id.take() {
+            Some(id) if !rotate => id,
+            old => {
+                if let Some(old) = old {
+                    self.store.delete(&old)?;
+                }
+                inner.record.rotated_at = now;
+                inner.modified = true;
+                generate_id()
+            }
+        };
+        let changed = inner.modified || !has_cookie;
+        inner.id = Some(id.clone());
+        inner.record.accessed_at = now;
+
+        if !changed && self.config.idle_timeout.is_none() {
+            return Ok(None);
+        }
+        self.store.save(&id, &inner.record)?;
+        Ok(Some(self.config.cookie(id, &inner.record, now)))
+    }
+}
+
+impl<St, S> Service<Request> for SessionService<St, S>
+where
+    St: SessionStore,
+    S: Service<Request, Response = Response, Error = ResponseError>,
+{
+    type Response = Response;
+    type Error = ResponseError;
+
+    fn call(&mut self, mut req: Request) -> Result<Self::Response, Self::Error> {
+        let now = Utc::now().timestamp();
+        let jar = CookieJar::parse(
+            req.headers
+                .get(&Headers::Cookie.to_string())
+                .unwrap_or_default(),
+        );
+        let id = jar
+            .get(&self.config.cookie_name)
+            .map(|cookie| cookie.value().to_string());
+
+        let record = match &id {
+            Some(id) => match self.load(id, now) {
+                Ok(record) => record,
+                Err(e) => return Ok(e.into_response()),
+            },
+            None => None,
+        };
+        let session = match record {
+            Some(record) => Session::new(id.clone(), record),
+            None => Session::new(
+                None,
+                SessionRecord {
+                    created_at: now,
+                    rotated_at: now,
+                    accessed_at: now,
+                    ..Default::default()
+                },
+            ),
+        };
+        req.extensions.insert(session.clone());
+
+        let resp = self.inner.call(req)?;
+        match self.save(&session, id.is_some(), now) {
+            Ok(Some(cookie)) => Ok(resp.cookie(&cookie)),
+            Ok(None) => Ok(resp),
+            Err(e) => Ok(e.into_response()),
+        }
+    }
+}
+
+impl IntoResponse for SessionError {
+    fn into_response(self) -> Response {
+        Response::new()
+            .status(StatusCode::InternalServerError)
+            .body(self.to_string().into_bytes())
+    }
+}
+
+/// 256 位随机会话 ID
+fn generate_id() -> String {
+    let mut bytes = [0; 32];
+    getrandom::getrandom(&mut bytes).expect("无法获取系统随机数");
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::SessionLayer;
+    use crate::{
+        Request, Router,
+        headers::Headers,
+        session::{MemoryStore, Session, SessionRecord, SessionStore},
+    };
+
+    fn counter(session: Session) -> String {
+        let n = session.get::<u32>("n").unwrap_or(0) + 1;
+        session.insert("n", n).unwrap();
+        n.to_string()
+    }
+
+    fn login(session: Session) -> &'static str {
+        session.rotate_id();
+        "ok"
+    }
+
+    fn logout(session: Session) -> &'static str {
+        session.destroy();
+        "bye"
+    }
+
+    fn app(layer: SessionLayer<MemoryStore>) -> Router {
+        Router::new()
+            .route("/", "GET", counter)
+            .route("/peek", "GET", |_: Request| "peek")
+            .route("/login", "GET", login)
+            .route("/logout", "GET", logout)
+            .layer(layer)
+    }
+
+    /// 返回响应体和 `Set-Cookie` 中的会话 ID
+    fn send(router: &mut Router, path: &str, id: Option<&str>) -> (String, Option<String>) {
+        let mut req = Request::new().path(path).unwrap();
+        if let Some(id) = id {
+            req = req.headers(Headers::Cookie, format!("session_id={id}"));
+        }
+        let resp: Vec<u8> = router.handle(req).into();
+        let resp = String::from_utf8(resp).unwrap();
+        let id = resp
+            .split("\r\n")
+            .find_map(|line| line.strip_prefix("Set-Cookie: session_id="))
+            .map(|cookie| cookie.split(';').next().unwrap().to_string());
+        let body = resp
+            .rsplit("\r\n\r\n")
+            .next()
+            .unwrap()
+            .trim_end()
+            .to_string();
+        (body, id)
+    }
+
+    #[test]
+    fn test_session() {
+        let store = MemoryStore::new();
+        let mut router = app(SessionLayer::new(store.clone()));
+
+        // 没有写入数据的会话不会保存
+        assert_eq!(send(&mut router, "/peek", None), ("peek".to_string(), None));
+
+        let (body, id) = send(&mut router, "/", None);
+        assert_eq!(body, "1");
+        let id = id.unwrap();
+        assert_eq!(send(&mut router, "/", Some(&id)).0, "2");
+        assert_eq!(send(&mut router, "/", Some("forged")).0, "1");
+
+        let (_, new_id) = send(&mut router, "/login", Some(&id));
+        let new_id = new_id.unwrap();
+        assert_ne!(new_id, id);
+        assert!(store.load(&id).unwrap().is_none());
+        assert_eq!(send(&mut router, "/", Some(&new_id)).0, "3");
+
+        let (_, removed) = send(&mut router, "/logout", Some(&new_id));
+        assert_eq!(removed.as_deref(), Some(""));
+        assert!(store.load(&new_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_expiry_and_rotation() {
+        let store = MemoryStore::new();
+        let now = Utc::now().timestamp();
+        let record = |created_at, rotated_at, accessed_at| {
+            let mut record = SessionRecord {
+                created_at,
+                rotated_at,
+                accessed_at,
+                ..Default::default()
+            };
+            record.data.insert("n".to_string(), 5.into());
+            record
+        };
+
+        let mut router = app(SessionLayer::new(store.clone()).idle_timeout(Duration::minutes(10)));
+        store.save("idle", &record(now, now, now - 601)).unwrap();
+        assert_eq!(send(&mut router, "/", Some("idle")).0, "1");
+        store.save("active", &record(now, now, now - 60)).unwrap();
+        assert_eq!(send(&mut router, "/", Some("active")).0, "6");
+
+        let mut router = app(SessionLayer::new(store.clone()).absolute_timeout(Duration::hours(1)));
+        store.save("old", &record(now - 3600, now, now)).unwrap();
+        assert_eq!(send(&mut router, "/", Some("old")).0, "1");
+
+        let mut router = app(SessionLayer::new(store.clone()).rotate_every(Duration::minutes(5)));
+        store.save("stale", &record(now, now - 300, now)).unwrap();
+        let (body, id) = send(&mut router, "/", Some("stale"));
+        assert_eq!(body, "6");
+        assert_ne!(id.as_deref(), Some("stale"));
+        assert!(store.load("stale").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_missing_layer() {
+        let mut router = Router::new().route("/", "GET", counter);
+        let resp: Vec<u8> = router.handle(Request::new()).into();
+        assert!(resp.starts_with(b"HTTP/1.1 500"));
+    }
+}
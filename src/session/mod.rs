@@ -0,0 +1,12 @@
+//! 会话
+//!
+//! [`SessionLayer`] 根据 cookie 中的会话 ID 从 [`SessionStore`] 加载会话,
+//! 处理函数通过 [`Session`] 提取器读写数据, 响应时保存会话并设置 cookie
+
+mod layer;
+mod session;
+mod store;
+
+pub use layer::{SessionLayer, SessionService};
+pub use session::Session;
+pub use store::{FileStore, MemoryStore, SessionRecord, SessionStore};
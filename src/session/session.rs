@@ -0,0 +1,125 @@
+//! 会话数据
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    error::SessionError,
+    extract::{FromRequestParts, Rejection},
+    request::Request,
+};
+
+use super::SessionRecord;
+
+#[derive(Debug)]
+pub(crate) struct Inner {
+    /// 请求中带来的会话 ID, 新会话为 `None`
+    pub(crate) id: Option<String>,
+    pub(crate) record: SessionRecord,
+    pub(crate) modified: bool,
+    pub(crate) rotate: bool,
+    pub(crate) destroyed: bool,
+}
+
+/// 当前请求的会话
+///
+/// 由 [`SessionLayer`](super::SessionLayer) 加载, 克隆后指向同一个会话,
+/// 处理函数返回后由中间件保存。值以 JSON 的形式保存, 读取时反序列化为需要的类型
+///
+/// # Example
+/// ```rust
+/// use http_sv::{
+///     Router,
+///     session::{MemoryStore, Session, SessionLayer},
+/// };
+///
+/// fn visit(session: Session) -> String {
+///     let n = session.get::<u32>("visits").unwrap_or(0) + 1;
+///     session.insert("visits", n).unwrap();
+///     format!("visit {n}")
+/// }
+///
+/// let app = Router::new()
+///     .route("/", "GET", visit)
+///     .layer(SessionLayer::new(MemoryStore::new()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session(pub(crate) Arc<Mutex<Inner>>);
+
+impl Session {
+    pub(crate) fn new(id: Option<String>, record: SessionRecord) -> Self {
+        Self(Arc::new(Mutex::new(Inner {
+            id,
+            record,
+            modified: false,
+            rotate: false,
+            destroyed: false,
+        })))
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 读取值, 不存在或类型不匹配时返回 `None`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.lock().record.data.get(key)?.clone();
+        serde_json::from_value(value).ok()
+    }
+
+    pub fn insert<T: Serialize>(&self, key: &str, value: T) -> Result<(), SessionError> {
+        let value = serde_json::to_value(value)?;
+        let mut inner = self.lock();
+        inner.record.data.insert(key.to_string(), value);
+        inner.modified = true;
+        Ok(())
+    }
+
+    /// 删除值并返回旧值
+    pub fn remove<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let mut inner = self.lock();
+        let value = inner.record.data.remove(key)?;
+        inner.modified = true;
+        serde_json::from_value(value).ok()
+    }
+
+    /// 清空所有值, 会话 ID 不变
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.record.data.clear();
+        inner.modified = true;
+    }
+
+    /// 响应时更换会话 ID, 数据保留, 登录等权限变化时应该调用以防止会话固定攻击
+    pub fn rotate_id(&self) {
+        self.lock().rotate = true;
+    }
+
+    /// 删除会话, 响应时从存储中删除并让客户端删除 cookie
+    pub fn destroy(&self) {
+        let mut inner = self.lock();
+        inner.record.data.clear();
+        inner.destroyed = true;
+    }
+
+    /// 请求中带来的会话 ID, 新会话为 `None`
+    pub fn id(&self) -> Option<String> {
+        self.lock().id.clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().record.data.is_empty()
+    }
+}
+
+impl FromRequestParts for Session {
+    type Rejection = Rejection;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        req.extensions
+            .get::<Session>()
+            .cloned()
+            .ok_or(Rejection::MissingSession)
+    }
+}
@@ -0,0 +1,169 @@
+//! 会话存储
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::SessionError;
+
+/// 保存在存储中的会话
+///
+/// 时间都是 Unix 时间戳(秒)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub data: HashMap<String, Value>,
+    /// 会话创建时间, 用于绝对过期
+    pub created_at: i64,
+    /// 当前 ID 的生成时间, 用于定期更换 ID
+    pub rotated_at: i64,
+    /// 最后一次访问时间, 用于空闲过期
+    pub accessed_at: i64,
+}
+
+/// 会话存储
+///
+/// 同一个存储会被多个服务副本同时使用, 实现需要自己处理并发
+pub trait SessionStore: Send + Sync + 'static {
+    fn load(&self, id: &str) -> Result<Option<SessionRecord>, SessionError>;
+
+    fn save(&self, id: &str, record: &SessionRecord) -> Result<(), SessionError>;
+
+    fn delete(&self, id: &str) -> Result<(), SessionError>;
+}
+
+/// 内存存储, 克隆后共享同一份数据, 进程退出后丢失
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    sessions: Arc<Mutex<HashMap<String, SessionRecord>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> Result<Option<SessionRecord>, SessionError> {
+        Ok(self.sessions.lock().unwrap().get(id).cloned())
+    }
+
+    fn save(&self, id: &str, record: &SessionRecord) -> Result<(), SessionError> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), record.clone());
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> Result<(), SessionError> {
+        self.sessions.lock().unwrap().remove(id);
+        Ok(())
+    }
+}
+
+/// 文件存储, 每个会话保存为目录下的一个 JSON 文件
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// 目录不存在时自动创建
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, SessionError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// 会话 ID 来自客户端, 只接受生成 ID 时使用的字符, 防止访问目录外的文件
+    fn path(&self, id: &str) -> Option<PathBuf> {
+        let valid = !id.is_empty()
+            && id
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+        valid.then(|| self.dir.join(format!("{id}.json")))
+    }
+}
+
+impl SessionStore for FileStore {
+    fn load(&self, id: &str) -> Result<Option<SessionRecord>, SessionError> {
+        let Some(path) = self.path(id) else {
+            return Ok(None);
+        };
+        match fs::read(path) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, id: &str, record: &SessionRecord) -> Result<(), SessionError> {
+        let Some(path) = self.path(id) else {
+            return Ok(());
+        };
+        // 先写临时文件再重命名, 避免并发读取到写了一半的文件
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(record)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> Result<(), SessionError> {
+        let Some(path) = self.path(id) else {
+            return Ok(());
+        };
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{FileStore, MemoryStore, SessionRecord, SessionStore};
+
+    fn check(store: &impl SessionStore) {
+        let mut record = SessionRecord {
+            created_at: 1,
+            rotated_at: 2,
+            accessed_at: 3,
+            ..Default::default()
+        };
+        record.data.insert("user".to_string(), json!("bing"));
+
+        assert_eq!(store.load("abc").unwrap(), None);
+        store.save("abc", &record).unwrap();
+        assert_eq!(store.load("abc").unwrap(), Some(record));
+        store.delete("abc").unwrap();
+        store.delete("abc").unwrap();
+        assert_eq!(store.load("abc").unwrap(), None);
+    }
+
+    #[test]
+    fn test_memory_store() {
+        check(&MemoryStore::new());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let dir = std::env::temp_dir().join(format!("http_sv-sessions-{}", std::process::id()));
+        let store = FileStore::new(&dir).unwrap();
+        check(&store);
+
+        store.save("../escape", &SessionRecord::default()).unwrap();
+        assert!(!dir.join("../escape.json").exists());
+        assert_eq!(store.load("../escape").unwrap(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,37 @@
+//! HTTP 日期
+//!
+//! 使用 RFC 9110 中的 IMF-fixdate 格式, 例如 `Sun, 06 Nov 1994 08:49:37 GMT`
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+const IMF_FIXDATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// 格式化为 IMF-fixdate
+pub fn format(time: DateTime<Utc>) -> String {
+    time.format(IMF_FIXDATE).to_string()
+}
+
+/// 解析 IMF-fixdate, 同时兼容 RFC 2822 格式
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    NaiveDateTime::parse_from_str(value, IMF_FIXDATE)
+        .map(|time| time.and_utc())
+        .or_else(|_| DateTime::parse_from_rfc2822(value).map(|time| time.to_utc()))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{format, parse};
+
+    #[test]
+    fn test_date() {
+        let time = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap();
+        assert_eq!(format(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49:37 GMT"), Some(time));
+        assert_eq!(parse("Sun, 6 Nov 1994 08:49:37 +0000"), Some(time));
+        assert_eq!(parse("yesterday"), None);
+    }
+}
@@ -1,6 +1,6 @@
-///! 工具
-///
-///
-///
-///
+//! 工具
+
+pub mod date;
 pub mod parse;
+pub mod percent;
+pub mod urlencoded;
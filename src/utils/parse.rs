@@ -14,22 +14,6 @@ pub(crate) const COLON: &[u8] = b": ";
 
 /// 解析分隔符 b"\r\n\r\n"
 /// 返回换行符之前和之后的内容
-///
-/// # Example
-/// ```rust
-/// const REQUEST: &[u8] = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\nHello,World\r\n";
-/// let (headers, body) = parse_separator(REQUEST).unwrap();
-///
-/// assert_eq!(headers, b"GET / HTTP/1.1\r\nHost: example.com");
-/// assert_eq!(body, b"Hello,World\r\n");
-///
-/// // body没有的时候
-/// REQUEST: &[u8] = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
-/// let (headers, body) = parse_separator(REQUEST).unwrap();
-///
-/// assert_eq!(headers, b"GET / HTTP/1.1\r\nHost: example.com");
-/// assert_eq!(body, b"");
-/// ```
 pub(crate) fn parse_separator(input: &[u8]) -> Result<(&[u8], &[u8]), ParseError> {
     match take_until(SEPARATOR)(input) {
         Ok((second, first)) => Ok((first, tag_consume(second, SEPARATOR)?)),
@@ -40,15 +24,6 @@ pub(crate) fn parse_separator(input: &[u8]) -> Result<(&[u8], &[u8]), ParseError
 
 /// 解析新行 b"\r\n"
 /// 返回 新行之前和之后的内容
-///
-/// # Example
-/// ```rust
-/// const REQUEST: &[u8] = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\nHello,World\r\n";
-/// let (headers, body) = parse_newline(REQUEST).unwrap();
-///
-/// assert_eq!(headers, b"GET / HTTP/1.1");
-/// assert_eq!(body, b"Host: example.com\r\n\r\nHello,World\r\n");
-/// ```
 pub(crate) fn parse_newline(input: &[u8]) -> Result<(&[u8], &[u8]), ParseError> {
     match take_until(NEWLINE)(input) {
         Ok((second, first)) => Ok((first, tag_consume(second, NEWLINE)?)),
@@ -59,17 +34,6 @@ pub(crate) fn parse_newline(input: &[u8]) -> Result<(&[u8], &[u8]), ParseError>
 
 /// 解析空格 b" "
 /// 返回空格前后的内容(不包括空格)
-///
-/// # Example
-/// ```rust
-/// const REQUEST: &[u8] = b"GET / HTTP/1.1";
-/// let (method, other) = parse_space(REQUEST).unwrap();
-/// let (path, version) = parse_space(other).unwrap();
-///
-/// assert_eq!(method, b"GET");
-/// assert_eq!(path, b"/");
-/// assert_eq!(version, b"HTTP/1.1");
-/// ```
 pub(crate) fn parse_space(input: &[u8]) -> Result<(&[u8], &[u8]), ParseError> {
     match take_until(SPACE)(input) {
         Ok((second, first)) => Ok((first, tag_consume(second, SPACE)?)),
@@ -80,28 +44,6 @@ pub(crate) fn parse_space(input: &[u8]) -> Result<(&[u8], &[u8]), ParseError> {
 
 /// 解析键值对 b": "
 /// 返回键值对 用于存储在hashmap中
-///
-/// # Example
-/// ```rust
-/// const HEADER: &[u8] = b"Host: example.com\r\nContent-Length: 12\r\nConnection: close\r\n";
-/// let mut header: HashMap<String, String> = HashMap::new();
-/// for iter in HEADER.lines() {
-///     match iter {
-///         Ok(i) => {
-///             let (k, v) = parse_map(i.as_bytes()).unwrap();
-///             header.insert(
-///                 String::from_utf8_lossy(k).to_string(),
-///                 String::from_utf8_lossy(v).to_string(),
-///             );
-///         }
-///         Err(_) => panic!(""),
-///     }
-/// }
-///
-/// assert_eq!(header.get(&"Host".to_string()),Some(&"example.com".to_string()));
-/// assert_eq!(header.get(&"Content-Length".to_string()),Some(&"12".to_string()));
-/// assert_eq!(header.get(&"Connection".to_string()),Some(&"close".to_string()));
-/// ```
 pub(crate) fn parse_map(input: &[u8]) -> Result<(&[u8], &[u8]), ParseError> {
     match take_until(COLON)(input) {
         Ok((second, first)) => Ok((first, tag_consume(second, COLON)?)),
@@ -182,15 +124,15 @@ mod tests {
         }
 
         assert_eq!(
-            header.get(&"Host".to_string()),
+            header.get("Host"),
             Some(&"example.com".to_string())
         );
         assert_eq!(
-            header.get(&"Content-Length".to_string()),
+            header.get("Content-Length"),
             Some(&"12".to_string())
         );
         assert_eq!(
-            header.get(&"Connection".to_string()),
+            header.get("Connection"),
             Some(&"close".to_string())
         );
     }
@@ -0,0 +1,729 @@
+//! application/x-www-form-urlencoded 解析、序列化与反序列化
+//!
+//! 查询字符串和表单共用同一套格式: `a=1&b=2&b=3`，重复的键可以反序列化为 `Vec`
+
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{
+    Deserializer, Serialize, Serializer,
+    de::{
+        self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+        value::StringDeserializer,
+    },
+    forward_to_deserialize_any,
+    ser::{self, Impossible, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple},
+};
+
+use crate::{
+    error::UrlEncodedError,
+    utils::percent::{percent_decode, percent_encode},
+};
+
+/// 解析为键值对，保留原始顺序, `+` 解码为空格
+pub(crate) fn parse(input: &str) -> Vec<(String, String)> {
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+fn decode(input: &str) -> String {
+    let input = input.replace('+', " ");
+    String::from_utf8_lossy(&percent_decode(input.as_bytes())).to_string()
+}
+
+fn encode(input: &str) -> String {
+    percent_encode(input).replace("%20", "+")
+}
+
+/// 把 `T` 序列化为 `a=1&b=2&b=3`, 按字段的声明顺序输出
+///
+/// `T` 必须序列化为 map 或结构体, 字段值为 `None` 时跳过, 序列展开为重复的键
+pub(crate) fn to_string<T: Serialize>(value: &T) -> Result<String, UrlEncodedError> {
+    let mut pairs = Vec::new();
+    value.serialize(FormSerializer {
+        pairs: &mut pairs,
+        key: None,
+    })?;
+
+    let encoded: Vec<String> = pairs
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", encode(&key), encode(&value)))
+        .collect();
+    Ok(encoded.join("&"))
+}
+
+macro_rules! serialize_display {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method(self, value: $ty) -> Result<Self::Ok, Self::Error> {
+                self.display(value)
+            }
+        )*
+    };
+}
+
+macro_rules! serialize_scalars {
+    () => {
+        serialize_display! {
+            serialize_bool(bool),
+            serialize_i8(i8),
+            serialize_i16(i16),
+            serialize_i32(i32),
+            serialize_i64(i64),
+            serialize_i128(i128),
+            serialize_u8(u8),
+            serialize_u16(u16),
+            serialize_u32(u32),
+            serialize_u64(u64),
+            serialize_u128(u128),
+            serialize_f32(f32),
+            serialize_f64(f64),
+            serialize_char(char),
+            serialize_str(&str),
+        }
+
+        fn serialize_bytes(self, _value: &[u8]) -> Result<Self::Ok, Self::Error> {
+            Err(ser::Error::custom("不支持字节序列"))
+        }
+
+        fn serialize_unit_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            variant: &'static str,
+        ) -> Result<Self::Ok, Self::Error> {
+            self.display(variant)
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            value: &T,
+        ) -> Result<Self::Ok, Self::Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _value: &T,
+        ) -> Result<Self::Ok, Self::Error> {
+            Err(ser::Error::custom("不支持嵌套的值"))
+        }
+
+        fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_tuple_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+            Err(ser::Error::custom("不支持嵌套的值"))
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+            Err(ser::Error::custom("不支持嵌套的值"))
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant, Self::Error> {
+            Err(ser::Error::custom("不支持嵌套的值"))
+        }
+    };
+}
+
+/// 顶层的 map 或结构体, 每个字段交给 [`FieldSerializer`]
+struct FormSerializer<'a> {
+    pairs: &'a mut Vec<(String, String)>,
+    /// map 中等待值的键
+    key: Option<String>,
+}
+
+impl FormSerializer<'_> {
+    fn display(self, _value: impl Display) -> Result<(), UrlEncodedError> {
+        Err(ser::Error::custom("只能序列化 map 或结构体"))
+    }
+}
+
+impl<'a> Serializer for FormSerializer<'a> {
+    type Ok = ();
+    type Error = UrlEncodedError;
+    type SerializeSeq = Impossible<(), UrlEncodedError>;
+    type SerializeTuple = Impossible<(), UrlEncodedError>;
+    type SerializeTupleStruct = Impossible<(), UrlEncodedError>;
+    type SerializeTupleVariant = Impossible<(), UrlEncodedError>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), UrlEncodedError>;
+
+    serialize_scalars!();
+
+    fn serialize_none(self) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+
+    fn serialize_unit(self) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, UrlEncodedError> {
+        Err(ser::Error::custom("只能序列化 map 或结构体"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, UrlEncodedError> {
+        Err(ser::Error::custom("只能序列化 map 或结构体"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self, UrlEncodedError> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, UrlEncodedError> {
+        Ok(self)
+    }
+}
+
+impl SerializeMap for FormSerializer<'_> {
+    type Ok = ();
+    type Error = UrlEncodedError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), UrlEncodedError> {
+        let key = key.serialize(ScalarSerializer)?;
+        self.key = Some(key.ok_or_else(|| ser::Error::custom("map 的键不能为空"))?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), UrlEncodedError> {
+        let key = self.key.take().unwrap_or_default();
+        value.serialize(FieldSerializer {
+            key: &key,
+            pairs: self.pairs,
+        })
+    }
+
+    fn end(self) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+}
+
+impl SerializeStruct for FormSerializer<'_> {
+    type Ok = ();
+    type Error = UrlEncodedError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), UrlEncodedError> {
+        value.serialize(FieldSerializer {
+            key,
+            pairs: self.pairs,
+        })
+    }
+
+    fn end(self) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+}
+
+/// 一个字段的值, 标量生成一个键值对, 序列的每个元素生成一个键值对
+struct FieldSerializer<'a> {
+    key: &'a str,
+    pairs: &'a mut Vec<(String, String)>,
+}
+
+impl FieldSerializer<'_> {
+    fn display(self, value: impl Display) -> Result<(), UrlEncodedError> {
+        self.pairs.push((self.key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+impl Serializer for FieldSerializer<'_> {
+    type Ok = ();
+    type Error = UrlEncodedError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Impossible<(), UrlEncodedError>;
+    type SerializeTupleVariant = Impossible<(), UrlEncodedError>;
+    type SerializeMap = Impossible<(), UrlEncodedError>;
+    type SerializeStruct = Impossible<(), UrlEncodedError>;
+    type SerializeStructVariant = Impossible<(), UrlEncodedError>;
+
+    serialize_scalars!();
+
+    fn serialize_none(self) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+
+    fn serialize_unit(self) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, UrlEncodedError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, UrlEncodedError> {
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, UrlEncodedError> {
+        Err(ser::Error::custom("不支持嵌套的值"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, UrlEncodedError> {
+        Err(ser::Error::custom("不支持嵌套的值"))
+    }
+}
+
+impl SerializeSeq for FieldSerializer<'_> {
+    type Ok = ();
+    type Error = UrlEncodedError;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), UrlEncodedError> {
+        let value = value.serialize(ScalarSerializer)?.unwrap_or_default();
+        self.pairs.push((self.key.to_string(), value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+}
+
+impl SerializeTuple for FieldSerializer<'_> {
+    type Ok = ();
+    type Error = UrlEncodedError;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), UrlEncodedError> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), UrlEncodedError> {
+        Ok(())
+    }
+}
+
+/// 序列中的元素和 map 的键, 只能是标量, `None` 序列化为 `None`
+struct ScalarSerializer;
+
+impl ScalarSerializer {
+    fn display(self, value: impl Display) -> Result<Option<String>, UrlEncodedError> {
+        Ok(Some(value.to_string()))
+    }
+}
+
+impl Serializer for ScalarSerializer {
+    type Ok = Option<String>;
+    type Error = UrlEncodedError;
+    type SerializeSeq = Impossible<Option<String>, UrlEncodedError>;
+    type SerializeTuple = Impossible<Option<String>, UrlEncodedError>;
+    type SerializeTupleStruct = Impossible<Option<String>, UrlEncodedError>;
+    type SerializeTupleVariant = Impossible<Option<String>, UrlEncodedError>;
+    type SerializeMap = Impossible<Option<String>, UrlEncodedError>;
+    type SerializeStruct = Impossible<Option<String>, UrlEncodedError>;
+    type SerializeStructVariant = Impossible<Option<String>, UrlEncodedError>;
+
+    serialize_scalars!();
+
+    fn serialize_none(self) -> Result<Option<String>, UrlEncodedError> {
+        Ok(None)
+    }
+
+    fn serialize_unit(self) -> Result<Option<String>, UrlEncodedError> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<String>, UrlEncodedError> {
+        Ok(None)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, UrlEncodedError> {
+        Err(ser::Error::custom("不支持嵌套的值"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, UrlEncodedError> {
+        Err(ser::Error::custom("不支持嵌套的值"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, UrlEncodedError> {
+        Err(ser::Error::custom("不支持嵌套的值"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, UrlEncodedError> {
+        Err(ser::Error::custom("不支持嵌套的值"))
+    }
+}
+
+/// 把多值 map 反序列化为 `T`
+pub(crate) fn from_map<T: DeserializeOwned>(
+    map: &HashMap<String, Vec<String>>,
+) -> Result<T, UrlEncodedError> {
+    T::deserialize(MapDeserializer {
+        iter: map.iter(),
+        value: None,
+    })
+}
+
+struct MapDeserializer<'a> {
+    iter: std::collections::hash_map::Iter<'a, String, Vec<String>>,
+    value: Option<&'a [String]>,
+}
+
+impl<'de> Deserializer<'de> for MapDeserializer<'_> {
+    type Error = UrlEncodedError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer<'_> {
+    type Error = UrlEncodedError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key: StringDeserializer<UrlEncodedError> = key.clone().into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = self.value.take().unwrap_or_default();
+        seed.deserialize(ValueDeserializer(value))
+    }
+}
+
+/// 一个键对应的所有值，目标类型是序列时全部使用，否则取最后一个
+struct ValueDeserializer<'a>(&'a [String]);
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = UrlEncodedError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_any(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(SeqDeserializer(self.0.iter()))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_bool(visitor)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_i8(visitor)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_i16(visitor)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_i32(visitor)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_i64(visitor)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_u8(visitor)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_u16(visitor)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_u32(visitor)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_u64(visitor)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_f32(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_f64(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.last().deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct newtype_struct
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl ValueDeserializer<'_> {
+    fn last(&self) -> PartDeserializer<'_> {
+        PartDeserializer(self.0.last().map(String::as_str).unwrap_or_default())
+    }
+}
+
+struct SeqDeserializer<'a>(std::slice::Iter<'a, String>);
+
+impl<'de> SeqAccess<'de> for SeqDeserializer<'_> {
+    type Error = UrlEncodedError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.0.next() {
+            Some(value) => seed.deserialize(PartDeserializer(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// 单个字符串值，按目标类型解析
+pub(crate) struct PartDeserializer<'a>(pub(crate) &'a str);
+
+macro_rules! deserialize_parse {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(e) => Err(de::Error::custom(format_args!("`{}`: {}", self.0, e))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for PartDeserializer<'_> {
+    type Error = UrlEncodedError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.0.to_string())
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value: StringDeserializer<UrlEncodedError> = self.0.to_string().into_deserializer();
+        visitor.visit_enum(value)
+    }
+
+    deserialize_parse! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{from_map, parse, to_string};
+
+    fn to_map(input: &str) -> HashMap<String, Vec<String>> {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for (k, v) in parse(input) {
+            map.entry(k).or_default().push(v);
+        }
+        map
+    }
+
+    #[test]
+    fn test_parse() {
+        let pairs = parse("q=hello+world&tag=a%26b&&flag");
+        assert_eq!(
+            pairs,
+            vec![
+                ("q".to_string(), "hello world".to_string()),
+                ("tag".to_string(), "a&b".to_string()),
+                ("flag".to_string(), "".to_string()),
+            ]
+        );
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Order {
+        Asc,
+        Desc,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Search {
+        q: String,
+        page: u32,
+        tag: Vec<String>,
+        order: Option<Order>,
+        debug: Option<bool>,
+    }
+
+    #[test]
+    fn test_from_map() {
+        let search: Search = from_map(&to_map("q=rust&page=2&tag=a&tag=b&order=desc")).unwrap();
+        assert_eq!(
+            search,
+            Search {
+                q: "rust".to_string(),
+                page: 2,
+                tag: vec!["a".to_string(), "b".to_string()],
+                order: Some(Order::Desc),
+                debug: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_from_map_error() {
+        assert!(from_map::<Search>(&to_map("q=rust&page=x")).is_err());
+        assert!(from_map::<Search>(&to_map("page=1")).is_err());
+    }
+
+    #[derive(Serialize)]
+    struct Login {
+        user: String,
+        roles: Vec<&'static str>,
+        remember: bool,
+        next: Option<String>,
+    }
+
+    #[test]
+    fn test_to_string() {
+        let login = Login {
+            user: "bing li".to_string(),
+            roles: vec!["a&b", "c"],
+            remember: true,
+            next: None,
+        };
+        // 按字段的声明顺序输出, 不按键排序
+        assert_eq!(
+            to_string(&login).unwrap(),
+            "user=bing+li&roles=a%26b&roles=c&remember=true"
+        );
+        assert!(to_string(&vec![1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_to_string_map() {
+        let map = BTreeMap::from([("a", vec![1, 2]), ("b", vec![3])]);
+        assert_eq!(to_string(&map).unwrap(), "a=1&a=2&b=3");
+
+        let nested = BTreeMap::from([("a", vec![vec![1]])]);
+        assert!(to_string(&nested).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! 帧的读写
+
+use std::io::{self, Read, Write};
+
+use crate::error::WebSocketError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OpCode {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl OpCode {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0x0 => Some(Self::Continuation),
+            0x1 => Some(Self::Text),
+            0x2 => Some(Self::Binary),
+            0x8 => Some(Self::Close),
+            0x9 => Some(Self::Ping),
+            0xA => Some(Self::Pong),
+            _ => None,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Self::Continuation => 0x0,
+            Self::Text => 0x1,
+            Self::Binary => 0x2,
+            Self::Close => 0x8,
+            Self::Ping => 0x9,
+            Self::Pong => 0xA,
+        }
+    }
+
+    fn is_control(self) -> bool {
+        matches!(self, Self::Close | Self::Ping | Self::Pong)
+    }
+}
+
+/// 控制帧的最大长度
+pub(crate) const MAX_CONTROL_PAYLOAD: usize = 125;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Frame {
+    pub(crate) fin: bool,
+    pub(crate) opcode: OpCode,
+    pub(crate) payload: Vec<u8>,
+}
+
+/// 读取客户端发来的帧, 客户端的帧必须带掩码, 负载超过 `max_size` 时不读取
+pub(crate) fn read_frame(r: &mut impl Read, max_size: usize) -> Result<Frame, WebSocketError> {
+    let mut head = [0; 2];
+    r.read_exact(&mut head)?;
+    let fin = head[0] & 0x80 != 0;
+    if head[0] & 0x70 != 0 {
+        return Err(WebSocketError::Protocol("没有协商扩展时 RSV 位必须为 0"));
+    }
+    let opcode = OpCode::from_u8(head[0] & 0x0F).ok_or(WebSocketError::Protocol("未知的操作码"))?;
+    if head[1] & 0x80 == 0 {
+        return Err(WebSocketError::Protocol("客户端发送的帧必须带掩码"));
+    }
+
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut len = [0; 2];
+            r.read_exact(&mut len)?;
+            u16::from_be_bytes(len) as u64
+        }
+        127 => {
+            let mut len = [0; 8];
+            r.read_exact(&mut len)?;
+            let len = u64::from_be_bytes(len);
+            if len >> 63 != 0 {
+                return Err(WebSocketError::Protocol("负载长度的最高位必须为 0"));
+            }
+            len
+        }
+        len => len as u64,
+    };
+    if opcode.is_control() {
+        if !fin {
+            return Err(WebSocketError::Protocol("控制帧不能分片"));
+        }
+        if len > MAX_CONTROL_PAYLOAD as u64 {
+            return Err(WebSocketError::Protocol("控制帧的负载不能超过 125 字节"));
+        }
+    }
+    if len > max_size as u64 {
+        return Err(WebSocketError::MessageTooLarge(max_size));
+    }
+
+    let mut mask = [0; 4];
+    r.read_exact(&mut mask)?;
+    let mut payload = vec![0; len as usize];
+    r.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok(Frame {
+        fin,
+        opcode,
+        payload,
+    })
+}
+
+/// 写入服务端的帧, 服务端的帧不带掩码
+pub(crate) fn write_frame(
+    w: &mut impl Write,
+    fin: bool,
+    opcode: OpCode,
+    payload: &[u8],
+) -> io::Result<()> {
+    let mut head = Vec::with_capacity(10);
+    head.push(((fin as u8) << 7) | opcode.as_u8());
+    match payload.len() {
+        len @ 0..=125 => head.push(len as u8),
+        len @ 126..=0xFFFF => {
+            head.push(126);
+            head.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            head.push(127);
+            head.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    w.write_all(&head)?;
+    w.write_all(payload)?;
+    w.flush()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{OpCode, read_frame, write_frame};
+    use crate::error::WebSocketError;
+
+    /// 客户端的帧
+    pub(crate) fn client_frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x37, 0xFA, 0x21, 0x3D];
+        let mut frame = vec![((fin as u8) << 7) | opcode];
+        match payload.len() {
+            len @ 0..=125 => frame.push(0x80 | len as u8),
+            len @ 126..=0xFFFF => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        frame
+    }
+
+    #[test]
+    fn test_read_frame() {
+        let data = client_frame(true, 0x1, b"Hello");
+        let frame = read_frame(&mut data.as_slice(), 1024).unwrap();
+        assert!(frame.fin);
+        assert_eq!(frame.opcode, OpCode::Text);
+        assert_eq!(frame.payload, b"Hello");
+
+        let long = vec![7; 70000];
+        let data = client_frame(false, 0x2, &long);
+        let frame = read_frame(&mut data.as_slice(), 100000).unwrap();
+        assert!(!frame.fin);
+        assert_eq!(frame.payload, long);
+        assert!(matches!(
+            read_frame(&mut data.as_slice(), 1000),
+            Err(WebSocketError::MessageTooLarge(1000))
+        ));
+
+        let protocol = |data: &[u8]| {
+            matches!(
+                read_frame(&mut &data[..], 1024),
+                Err(WebSocketError::Protocol(_))
+            )
+        };
+        // 没有掩码
+        assert!(protocol(&[0x81, 0x01, b'a']));
+        assert!(protocol(&client_frame(true, 0x3, b"")));
+        assert!(protocol(&client_frame(false, 0x9, b"")));
+        assert!(protocol(&client_frame(true, 0x9, &[0; 126])));
+        assert!(protocol(&client_frame(true, 0x1 | 0x40, b"")));
+    }
+
+    #[test]
+    fn test_write_frame() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, true, OpCode::Text, b"Hello").unwrap();
+        assert_eq!(buf, b"\x81\x05Hello");
+
+        let mut buf = Vec::new();
+        write_frame(&mut buf, true, OpCode::Binary, &[0; 256]).unwrap();
+        assert_eq!(&buf[..4], &[0x82, 126, 0x01, 0x00]);
+        assert_eq!(buf.len(), 4 + 256);
+    }
+}
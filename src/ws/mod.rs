@@ -0,0 +1,11 @@
+//! WebSocket
+//!
+//! [`WebSocketUpgrade`] 完成 RFC 6455 的握手并返回 101 响应,
+//! 响应写入后连接交给处理函数, 通过 [`WebSocket`] 收发消息
+
+mod frame;
+mod socket;
+mod upgrade;
+
+pub use socket::{CloseFrame, Message, WebSocket, close_code};
+pub use upgrade::WebSocketUpgrade;
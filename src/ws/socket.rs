@@ -0,0 +1,369 @@
+//! 握手完成后的连接
+
+use std::io::{BufReader, Read, Write};
+
+use crate::error::WebSocketError;
+
+use super::frame::{self, MAX_CONTROL_PAYLOAD, OpCode};
+
+/// 常用的关闭码
+pub mod close_code {
+    /// 正常关闭
+    pub const NORMAL: u16 = 1000;
+    /// 服务端关闭或页面离开
+    pub const AWAY: u16 = 1001;
+    /// 协议错误
+    pub const PROTOCOL: u16 = 1002;
+    /// 不支持的数据类型
+    pub const UNSUPPORTED: u16 = 1003;
+    /// 数据和消息类型不一致, 例如文本消息不是 UTF-8
+    pub const INVALID: u16 = 1007;
+    /// 违反策略
+    pub const POLICY: u16 = 1008;
+    /// 消息过大
+    pub const SIZE: u16 = 1009;
+    /// 服务端错误
+    pub const ERROR: u16 = 1011;
+}
+
+/// 关闭帧中的关闭码和原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    pub code: u16,
+    pub reason: String,
+}
+
+impl CloseFrame {
+    pub fn new(code: u16, reason: impl Into<String>) -> Self {
+        Self {
+            code,
+            reason: reason.into(),
+        }
+    }
+
+    fn parse(payload: &[u8]) -> Result<Option<Self>, WebSocketError> {
+        match payload {
+            [] => Ok(None),
+            [_] => Err(WebSocketError::Protocol("关闭帧的负载不能只有 1 字节")),
+            [a, b, reason @ ..] => {
+                let code = u16::from_be_bytes([*a, *b]);
+                if !matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999) {
+                    return Err(WebSocketError::Protocol("关闭码无效"));
+                }
+                let reason =
+                    String::from_utf8(reason.to_vec()).map_err(|_| WebSocketError::InvalidUtf8)?;
+                Ok(Some(Self { code, reason }))
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut payload = self.code.to_be_bytes().to_vec();
+        payload.extend_from_slice(self.reason.as_bytes());
+        payload
+    }
+}
+
+/// 一条完整的消息, 分片的消息会被合并
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close(Option<CloseFrame>),
+}
+
+/// WebSocket 连接
+///
+/// 收到 `Ping` 时自动回复 `Pong`, 收到关闭帧时自动回复关闭帧。
+/// 协议错误时发送对应关闭码的关闭帧并返回错误, 之后的读写都返回
+/// [`WebSocketError::ConnectionClosed`]
+pub struct WebSocket {
+    reader: BufReader<Box<dyn Read + Send>>,
+    writer: Box<dyn Write + Send>,
+    protocol: Option<String>,
+    max_message_size: usize,
+    /// 正在接收的分片消息
+    fragment: Option<(OpCode, Vec<u8>)>,
+    sent_close: bool,
+    received_close: bool,
+}
+
+impl WebSocket {
+    pub(crate) fn new(
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+        protocol: Option<String>,
+        max_message_size: usize,
+    ) -> Self {
+        Self {
+            reader: BufReader::new(Box::new(reader)),
+            writer: Box::new(writer),
+            protocol,
+            max_message_size,
+            fragment: None,
+            sent_close: false,
+            received_close: false,
+        }
+    }
+
+    /// 握手时选择的子协议
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// 接收下一条消息
+    pub fn recv(&mut self) -> Result<Message, WebSocketError> {
+        if self.received_close {
+            return Err(WebSocketError::ConnectionClosed);
+        }
+        loop {
+            let frame = match frame::read_frame(&mut self.reader, self.max_message_size) {
+                Ok(frame) => frame,
+                Err(e) => return Err(self.fail(e)),
+            };
+            match frame.opcode {
+                OpCode::Ping => {
+                    if !self.sent_close {
+                        frame::write_frame(&mut self.writer, true, OpCode::Pong, &frame.payload)?;
+                    }
+                    return Ok(Message::Ping(frame.payload));
+                }
+                OpCode::Pong => return Ok(Message::Pong(frame.payload)),
+                OpCode::Close => {
+                    let close = match CloseFrame::parse(&frame.payload) {
+                        Ok(close) => close,
+                        Err(e) => return Err(self.fail(e)),
+                    };
+                    self.received_close = true;
+                    if !self.sent_close {
+                        // 回复相同的关闭码
+                        self.send(Message::Close(close.clone()))?;
+                    }
+                    return Ok(Message::Close(close));
+                }
+                OpCode::Text | OpCode::Binary => {
+                    if self.fragment.is_some() {
+                        let e = WebSocketError::Protocol("上一条分片消息还没有结束");
+                        return Err(self.fail(e));
+                    }
+                    if frame.fin {
+                        return self.message(frame.opcode, frame.payload);
+                    }
+                    self.fragment = Some((frame.opcode, frame.payload));
+                }
+                OpCode::Continuation => {
+                    let Some((_, data)) = self.fragment.as_mut() else {
+                        let e = WebSocketError::Protocol("没有需要继续的分片消息");
+                        return Err(self.fail(e));
+                    };
+                    if data.len() + frame.payload.len() > self.max_message_size {
+                        let e = WebSocketError::MessageTooLarge(self.max_message_size);
+                        return Err(self.fail(e));
+                    }
+                    data.extend_from_slice(&frame.payload);
+                    if frame.fin {
+                        let (opcode, data) = self.fragment.take().unwrap();
+                        return self.message(opcode, data);
+                    }
+                }
+            }
+        }
+    }
+
+    fn message(&mut self, opcode: OpCode, data: Vec<u8>) -> Result<Message, WebSocketError> {
+        match opcode {
+            OpCode::Text => match String::from_utf8(data) {
+                Ok(text) => Ok(Message::Text(text)),
+                Err(_) => Err(self.fail(WebSocketError::InvalidUtf8)),
+            },
+            _ => Ok(Message::Binary(data)),
+        }
+    }
+
+    /// 协议错误时发送关闭帧, 不再读取
+    fn fail(&mut self, e: WebSocketError) -> WebSocketError {
+        let code = match &e {
+            WebSocketError::Protocol(_) => close_code::PROTOCOL,
+            WebSocketError::InvalidUtf8 => close_code::INVALID,
+            WebSocketError::MessageTooLarge(_) => close_code::SIZE,
+            _ => return e,
+        };
+        if !self.sent_close {
+            let _ = self.send(Message::Close(Some(CloseFrame::new(code, ""))));
+        }
+        self.received_close = true;
+        e
+    }
+
+    /// 发送消息, 发送关闭帧后不能再发送
+    pub fn send(&mut self, message: Message) -> Result<(), WebSocketError> {
+        if self.sent_close {
+            return Err(WebSocketError::ConnectionClosed);
+        }
+        let (opcode, payload) = match message {
+            Message::Text(text) => (OpCode::Text, text.into_bytes()),
+            Message::Binary(data) => (OpCode::Binary, data),
+            Message::Ping(data) => (OpCode::Ping, data),
+            Message::Pong(data) => (OpCode::Pong, data),
+            Message::Close(close) => (
+                OpCode::Close,
+                close.map(|c| c.to_bytes()).unwrap_or_default(),
+            ),
+        };
+        if opcode != OpCode::Text && opcode != OpCode::Binary && payload.len() > MAX_CONTROL_PAYLOAD
+        {
+            return Err(WebSocketError::Protocol("控制帧的负载不能超过 125 字节"));
+        }
+        // 负载检查通过后才算发送了关闭帧, 失败时仍然可以重新发送
+        if opcode == OpCode::Close {
+            self.sent_close = true;
+        }
+        frame::write_frame(&mut self.writer, true, opcode, &payload)?;
+        Ok(())
+    }
+
+    /// 发送关闭帧并等待客户端回复关闭帧, 期间收到的消息被丢弃
+    pub fn close(mut self, close: Option<CloseFrame>) -> Result<(), WebSocketError> {
+        if !self.sent_close {
+            self.send(Message::Close(close))?;
+        }
+        while !self.received_close {
+            self.recv()?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for WebSocket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebSocket")
+            .field("protocol", &self.protocol)
+            .field("sent_close", &self.sent_close)
+            .field("received_close", &self.received_close)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use super::{CloseFrame, Message, WebSocket};
+    use crate::{error::WebSocketError, ws::frame::tests::client_frame};
+
+    /// 记录服务端写入的数据
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn socket(frames: &[Vec<u8>]) -> (WebSocket, Output) {
+        let output = Output::default();
+        let input = std::io::Cursor::new(frames.concat());
+        (WebSocket::new(input, output.clone(), None, 1024), output)
+    }
+
+    #[test]
+    fn test_messages() {
+        let (mut ws, output) = socket(&[
+            client_frame(false, 0x1, "你".as_bytes()),
+            client_frame(true, 0x9, b"ping"),
+            client_frame(false, 0x0, "好".as_bytes()),
+            client_frame(true, 0x0, b"!"),
+            client_frame(true, 0x2, &[1, 2]),
+            client_frame(true, 0x8, &[0x03, 0xE8, b'b', b'y', b'e']),
+        ]);
+        assert_eq!(ws.recv().unwrap(), Message::Ping(b"ping".to_vec()));
+        assert_eq!(ws.recv().unwrap(), Message::Text("你好!".to_string()));
+        assert_eq!(ws.recv().unwrap(), Message::Binary(vec![1, 2]));
+        assert_eq!(
+            ws.recv().unwrap(),
+            Message::Close(Some(CloseFrame::new(1000, "bye")))
+        );
+        assert!(matches!(ws.recv(), Err(WebSocketError::ConnectionClosed)));
+        assert!(matches!(
+            ws.send(Message::Text("late".to_string())),
+            Err(WebSocketError::ConnectionClosed)
+        ));
+        // 自动回复的 Pong 和关闭帧
+        assert_eq!(
+            *output.0.lock().unwrap(),
+            b"\x8A\x04ping\x88\x05\x03\xE8bye".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let cases = [
+            (vec![client_frame(true, 0x0, b"a")], 1002),
+            (
+                vec![
+                    client_frame(false, 0x1, b"a"),
+                    client_frame(true, 0x1, b"b"),
+                ],
+                1002,
+            ),
+            (vec![client_frame(true, 0x1, &[0xFF])], 1007),
+            (vec![client_frame(true, 0x2, &[0; 2000])], 1009),
+            (
+                vec![
+                    client_frame(false, 0x2, &[0; 1000]),
+                    client_frame(true, 0x0, &[0; 100]),
+                ],
+                1009,
+            ),
+            (vec![client_frame(true, 0x8, &[0x03])], 1002),
+            (vec![client_frame(true, 0x8, &[0x03, 0xED])], 1002),
+        ];
+        for (frames, code) in cases {
+            let (mut ws, output) = socket(&frames);
+            assert!(ws.recv().is_err());
+            let output = output.0.lock().unwrap().clone();
+            assert_eq!(output[..2], [0x88, 0x02]);
+            assert_eq!(u16::from_be_bytes([output[2], output[3]]), code);
+            assert!(matches!(ws.recv(), Err(WebSocketError::ConnectionClosed)));
+        }
+    }
+
+    #[test]
+    fn test_close() {
+        let (ws, output) = socket(&[
+            client_frame(true, 0x1, b"in flight"),
+            client_frame(true, 0x8, &[]),
+        ]);
+        ws.close(Some(CloseFrame::new(1001, "restart"))).unwrap();
+        assert_eq!(
+            *output.0.lock().unwrap(),
+            b"\x88\x09\x03\xE9restart".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_close_reason_too_long() {
+        let (mut ws, output) = socket(&[client_frame(true, 0x8, &[])]);
+        let reason = "x".repeat(124);
+        let err = ws
+            .send(Message::Close(Some(CloseFrame::new(1000, &reason))))
+            .unwrap_err();
+        assert!(matches!(err, WebSocketError::Protocol(_)));
+        assert!(output.0.lock().unwrap().is_empty());
+
+        // 失败后仍然可以发送关闭帧
+        ws.close(Some(CloseFrame::new(1000, "bye"))).unwrap();
+        assert_eq!(*output.0.lock().unwrap(), b"\x88\x05\x03\xE8bye".to_vec());
+    }
+}
@@ -0,0 +1,313 @@
+//! 握手
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sha1::{Digest, Sha1};
+
+use crate::{
+    extract::{FromRequestParts, Rejection},
+    headers::{Headers, HttpMethod, HttpVersion, StatusCode},
+    request::Request,
+    response::Response,
+};
+
+use super::WebSocket;
+
+/// 计算 `Sec-WebSocket-Accept` 时使用的 GUID
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// WebSocket 握手请求
+///
+/// 作为提取器检查 `Upgrade`、`Connection`、`Sec-WebSocket-Key` 和 `Sec-WebSocket-Version`,
+/// 不是合法的握手请求时返回 400, 版本不是 13 时返回 426。
+/// [`on_upgrade`](Self::on_upgrade) 返回 101 响应, 响应写入后在连接的线程中调用回调
+///
+/// # Example
+/// ```rust
+/// use http_sv::{
+///     Router,
+///     response::Response,
+///     ws::{Message, WebSocketUpgrade},
+/// };
+///
+/// fn echo(ws: WebSocketUpgrade) -> Response {
+///     ws.protocols(["echo"]).on_upgrade(|mut socket| {
+///         while let Ok(message) = socket.recv() {
+///             let reply = match message {
+///                 Message::Text(text) => Message::Text(text),
+///                 Message::Binary(data) => Message::Binary(data),
+///                 Message::Close(_) => break,
+///                 _ => continue,
+///             };
+///             if socket.send(reply).is_err() {
+///                 break;
+///             }
+///         }
+///     })
+/// }
+///
+/// let app = Router::new().route("/ws", "GET", echo);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WebSocketUpgrade {
+    key: String,
+    /// 客户端请求的子协议
+    requested: Vec<String>,
+    protocol: Option<String>,
+    max_message_size: usize,
+}
+
+impl WebSocketUpgrade {
+    /// 服务端支持的子协议, 按服务端的顺序选择第一个客户端也请求了的
+    pub fn protocols<I, T>(mut self, protocols: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.protocol = protocols
+            .into_iter()
+            .map(Into::into)
+            .find(|protocol| self.requested.contains(protocol));
+        self
+    }
+
+    /// 单条消息的最大长度, 默认 16 MB
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// 返回 101 响应, 响应写入后把连接交给 `callback`
+    pub fn on_upgrade<F>(self, callback: F) -> Response
+    where
+        F: FnOnce(WebSocket) + Send + 'static,
+    {
+        let mut resp = Response::new()
+            .status(StatusCode::SwitchingProtocols)
+            .header(Headers::Upgrade, "websocket")
+            .header(Headers::Connection, "Upgrade")
+            .header(Headers::SecWebSocketAccept, &accept_key(&self.key))
+            .remove_header(Headers::ContentLength)
+            .remove_header(Headers::ContentType);
+        if let Some(protocol) = &self.protocol {
+            resp = resp.header(Headers::SecWebSocketProtocol, protocol);
+        }
+        let Self {
+            protocol,
+            max_message_size,
+            ..
+        } = self;
+        resp.on_upgrade(move |stream| {
+            let Ok(writer) = stream.try_clone_stream() else {
+                return;
+            };
+            callback(WebSocket::new(stream, writer, protocol, max_message_size));
+        })
+    }
+}
+
+/// `Sec-WebSocket-Accept` 的值
+fn accept_key(key: &str) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.update(key.as_bytes());
+    sha1.update(GUID.as_bytes());
+    STANDARD.encode(sha1.finalize())
+}
+
+/// 逗号分隔的头部是否包含 `token`, 不区分大小写
+fn has_token(req: &Request, key: Headers, token: &str) -> bool {
+    req.headers.get(&key.to_string()).is_some_and(|value| {
+        value
+            .split(',')
+            .any(|v| v.trim().eq_ignore_ascii_case(token))
+    })
+}
+
+impl FromRequestParts for WebSocketUpgrade {
+    type Rejection = Rejection;
+
+    fn from_request_parts(req: &mut Request) -> Result<Self, Self::Rejection> {
+        let invalid = Rejection::InvalidWebSocketUpgrade;
+        if *req.method_ref() != HttpMethod::GET || matches!(req.version_ref(), HttpVersion::V1_0) {
+            return Err(invalid("必须是 HTTP/1.1 的 GET 请求"));
+        }
+        if !has_token(req, Headers::Connection, "upgrade") {
+            return Err(invalid("Connection 中缺少 upgrade"));
+        }
+        if !has_token(req, Headers::Upgrade, "websocket") {
+            return Err(invalid("Upgrade 必须是 websocket"));
+        }
+        let version = req.headers.get(&Headers::SecWebSocketVersion.to_string());
+        if version.map(str::trim) != Some("13") {
+            return Err(Rejection::UnsupportedWebSocketVersion);
+        }
+        let key = req
+            .headers
+            .get(&Headers::SecWebSocketKey.to_string())
+            .map(str::trim)
+            .filter(|key| STANDARD.decode(key).is_ok_and(|key| key.len() == 16))
+            .ok_or(invalid("Sec-WebSocket-Key 必须是 16 字节的 base64"))?;
+        let requested = req
+            .headers
+            .get(&Headers::SecWebSocketProtocol.to_string())
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            key: key.to_string(),
+            requested,
+            protocol: None,
+            max_message_size: 16 * 1024 * 1024,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use super::{WebSocketUpgrade, accept_key};
+    use crate::{
+        Request, Router,
+        headers::Headers,
+        response::Response,
+        ws::{Message, frame::tests::client_frame},
+    };
+
+    fn echo(ws: WebSocketUpgrade) -> Response {
+        ws.protocols(["echo", "chat"]).on_upgrade(|mut socket| {
+            let protocol = socket.protocol().unwrap_or_default().to_string();
+            socket.send(Message::Text(protocol)).unwrap();
+            while let Ok(message) = socket.recv() {
+                match message {
+                    Message::Text(text) => socket.send(Message::Text(text)).unwrap(),
+                    Message::Binary(data) => socket.send(Message::Binary(data)).unwrap(),
+                    Message::Close(_) => break,
+                    _ => {}
+                }
+            }
+        })
+    }
+
+    fn handshake() -> Request {
+        Request::new()
+            .path("/ws")
+            .unwrap()
+            .headers(Headers::Connection, "keep-alive, Upgrade")
+            .headers(Headers::Upgrade, "websocket")
+            .headers(Headers::SecWebSocketVersion, "13")
+            .headers(Headers::SecWebSocketKey, "dGhlIHNhbXBsZSBub25jZQ==")
+    }
+
+    fn send(router: &mut Router, req: Request) -> String {
+        let resp: Vec<u8> = router.handle(req).into();
+        String::from_utf8(resp).unwrap()
+    }
+
+    #[test]
+    fn test_handshake() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        let mut router = Router::new().route("/ws", "GET", echo);
+
+        let req = handshake().headers(Headers::SecWebSocketProtocol, "chat, echo");
+        let resp = send(&mut router, req);
+        assert!(resp.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(resp.contains("Upgrade: websocket\r\n"));
+        assert!(resp.contains("Connection: Upgrade\r\n"));
+        assert!(resp.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+        assert!(resp.contains("Sec-WebSocket-Protocol: echo\r\n"));
+        assert!(!resp.contains("Content-Length"));
+
+        let resp = send(&mut router, handshake().headers(Headers::Upgrade, "h2c"));
+        assert!(resp.starts_with("HTTP/1.1 400"));
+        let resp = send(
+            &mut router,
+            handshake().headers(Headers::SecWebSocketKey, "abc"),
+        );
+        assert!(resp.starts_with("HTTP/1.1 400"));
+        let resp = send(
+            &mut router,
+            handshake().headers(Headers::SecWebSocketVersion, "8"),
+        );
+        assert!(resp.starts_with("HTTP/1.1 426"));
+        assert!(resp.contains("Sec-WebSocket-Version: 13\r\n"));
+    }
+
+    /// 读取一个服务端的帧, 返回操作码和负载
+    fn read_server_frame(r: &mut impl Read) -> (u8, Vec<u8>) {
+        let mut head = [0; 2];
+        r.read_exact(&mut head).unwrap();
+        assert_eq!(head[1] & 0x80, 0, "服务端的帧不能带掩码");
+        let len = match head[1] {
+            126 => {
+                let mut len = [0; 2];
+                r.read_exact(&mut len).unwrap();
+                u16::from_be_bytes(len) as usize
+            }
+            len => len as usize,
+        };
+        let mut payload = vec![0; len];
+        r.read_exact(&mut payload).unwrap();
+        (head[0], payload)
+    }
+
+    #[test]
+    fn test_echo_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/ws", "GET", echo);
+        thread::spawn(move || crate::serve(listener, app));
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(
+                b"GET /ws HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\n\
+                  Upgrade: websocket\r\nSec-WebSocket-Version: 13\r\n\
+                  Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                  Sec-WebSocket-Protocol: chat\r\n\r\n",
+            )
+            .unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "HTTP/1.1 101 Switching Protocols\r\n");
+        while line != "\r\n" {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+
+        assert_eq!(read_server_frame(&mut reader), (0x81, b"chat".to_vec()));
+        client.write_all(&client_frame(false, 0x1, b"hel")).unwrap();
+        client.write_all(&client_frame(true, 0x9, b"p")).unwrap();
+        client.write_all(&client_frame(true, 0x0, b"lo")).unwrap();
+        assert_eq!(read_server_frame(&mut reader), (0x8A, b"p".to_vec()));
+        assert_eq!(read_server_frame(&mut reader), (0x81, b"hello".to_vec()));
+
+        let data = vec![9; 300];
+        client.write_all(&client_frame(true, 0x2, &data)).unwrap();
+        assert_eq!(read_server_frame(&mut reader), (0x82, data));
+
+        client
+            .write_all(&client_frame(true, 0x8, &1000u16.to_be_bytes()))
+            .unwrap();
+        assert_eq!(
+            read_server_frame(&mut reader),
+            (0x88, 1000u16.to_be_bytes().to_vec())
+        );
+        // 处理函数返回后服务端关闭连接
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert!(rest.is_empty());
+    }
+}